@@ -0,0 +1,2636 @@
+//! Analysis of embedded trajectories
+//!
+//! Functions in this module embed a scalar series with the given delay-coordinates and compute
+//! quantities on the resulting trajectory. Indices of points refer to windows of the series.
+//! A Theiler window `theiler` excludes pairs of points whose indices differ by at most `theiler`,
+//! so `theiler = 0` excludes only self-pairs.
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::f64::consts::PI;
+
+use crate::{DelayCoordinates, Error, ForwardDelayCoordinates};
+use crate::dimension::fit_slope;
+use crate::linalg::symmetric_eigen;
+use crate::metric::Metric;
+use crate::preprocess::{RunningStats, is_degenerate};
+use crate::rng::Xorshift;
+
+#[cfg(feature = "fft")]
+mod fft;
+
+/// Calculates the distance between the `i`-th and `j`-th embedded points
+///
+/// # Panics
+///
+/// Panics if `i` or `j` is not an index of an embedded point.
+pub fn distance<C>(coord: &C, slice: &[f64], i: usize, j: usize, metric: Metric) -> f64
+where
+    C: DelayCoordinates,
+{
+    let p = point(coord, slice, i);
+    let q = point(coord, slice, j);
+    metric.distance(&p, &q)
+}
+
+/// Calculates the distances from the `i`-th embedded point to all embedded points
+///
+/// The windows are swept in order into a single buffer without collecting the trajectory.
+///
+/// # Panics
+///
+/// Panics if `i` is not an index of an embedded point.
+pub fn distances_from<C>(coord: &C, slice: &[f64], i: usize, metric: Metric) -> Vec<f64>
+where
+    C: DelayCoordinates,
+{
+    let p = point(coord, slice, i);
+    let mut q = Vec::with_capacity(p.len());
+    coord.mapping_iter(slice)
+        .map(|view| {
+            q.clear();
+            q.extend((0..p.len()).map(|k| view[k]));
+            metric.distance(&p, &q)
+        })
+        .collect()
+}
+
+/// Returns the `i`-th embedded point
+fn point<C>(coord: &C, slice: &[f64], i: usize) -> Vec<f64>
+where
+    C: DelayCoordinates,
+{
+    coord.mapping_iter(slice)
+        .nth(i)
+        .expect("index of an embedded point is out of range")
+        .to_vec()
+}
+
+/// Counts the neighbors of each embedded point within `radius`
+///
+/// This is the per-point version of the recurrence rate.
+pub fn neighbor_counts<C>(coord: &C, slice: &[f64], radius: f64, metric: Metric, theiler: usize) -> Vec<usize>
+where
+    C: DelayCoordinates,
+{
+    let points = crate::embed(coord, slice);
+    (0..points.len())
+        .map(|i| {
+            (0..points.len())
+                .filter(|&j| i.abs_diff(j) > theiler)
+                .filter(|&j| metric.distance(&points[i], &points[j]) <= radius)
+                .count()
+        })
+        .collect()
+}
+
+/// Counts the neighbors of each point within `radius` from precomputed distances
+///
+/// This is the same as `neighbor_counts` but does not recompute the distances.
+pub fn neighbor_counts_cached(cache: &DistanceCache, radius: f64, theiler: usize) -> Vec<usize> {
+    (0..cache.len())
+        .map(|i| {
+            (0..cache.len())
+                .filter(|&j| i.abs_diff(j) > theiler)
+                .filter(|&j| cache.get(i, j) <= radius)
+                .count()
+        })
+        .collect()
+}
+
+/// Pairwise distances of an embedded trajectory
+///
+/// The distances are computed once and stored as the upper triangle of the distance matrix,
+/// so analyses on the same embedding can share them instead of recomputing them.
+#[derive(Debug, Clone)]
+pub struct DistanceCache {
+    len: usize,
+    metric: Metric,
+    distances: Vec<f64>,
+}
+
+impl DistanceCache {
+    /// Computes the pairwise distances of the embedded series
+    pub fn new<C>(coord: &C, slice: &[f64], metric: Metric) -> Self
+    where
+        C: DelayCoordinates,
+    {
+        Self::from_points(&crate::embed(coord, slice), metric)
+    }
+
+    /// Computes the pairwise distances of the given points
+    pub fn from_points(points: &[Vec<f64>], metric: Metric) -> Self {
+        let len = points.len();
+        let mut distances = Vec::with_capacity(len * len.saturating_sub(1) / 2);
+        for (i, p) in points.iter().enumerate() {
+            distances.extend(points[i+1..].iter().map(|q| metric.distance(p, q)));
+        }
+        DistanceCache {
+            len,
+            metric,
+            distances,
+        }
+    }
+
+    /// Number of points
+    #[inline]
+    pub fn len(&self) -> usize { self.len }
+
+    /// Returns `true` if there are no points
+    #[inline]
+    pub fn is_empty(&self) -> bool { self.len == 0 }
+
+    /// Metric the distances were computed with
+    #[inline]
+    pub fn metric(&self) -> Metric { self.metric }
+
+    /// Distance between the `i`-th and the `j`-th points
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i` or `j` is out of range.
+    pub fn get(&self, i: usize, j: usize) -> f64 {
+        assert!(i < self.len && j < self.len, "index of an embedded point is out of range");
+        if i == j {
+            return 0.0;
+        }
+        let (i, j) = if i < j { (i, j) } else { (j, i) };
+        self.distances[i * (2 * self.len - i - 1) / 2 + (j - i - 1)]
+    }
+}
+
+/// Rule to choose among equidistant neighbors
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TieBreak {
+    /// Prefers the neighbor with the smallest index
+    FirstIndex,
+    /// Prefers the neighbor with the largest index
+    LastIndex,
+    /// Prefers the neighbor farthest in time from the reference point
+    FarthestInTime,
+}
+
+/// Brute-force neighbor search on an embedded trajectory
+///
+/// Neighbors within the Theiler window `theiler` of the reference point are excluded and ties in
+/// distance are resolved by `tie_break`, so the result does not depend on the scanning order.
+/// If `skip_zero` is true, neighbors at distance zero are excluded as well, so that flat segments
+/// of the series do not dominate the neighborhoods.
+/// Neighbors at a NaN distance are excluded, so a NaN sample only removes the windows containing it.
+#[derive(Debug, Clone)]
+pub struct NeighborSearch {
+    pub metric: Metric,
+    pub theiler: usize,
+    pub tie_break: TieBreak,
+    pub skip_zero: bool,
+}
+
+impl NeighborSearch {
+    /// Creates a search excluding only self-matches and preferring the first index on ties
+    pub fn new(metric: Metric) -> Self {
+        NeighborSearch {
+            metric,
+            theiler: 0,
+            tie_break: TieBreak::FirstIndex,
+            skip_zero: false,
+        }
+    }
+
+    /// Finds the nearest neighbor of the `i`-th point
+    pub fn nearest(&self, points: &[Vec<f64>], i: usize) -> Option<usize> {
+        self.candidates(points, i)
+            .min_by(|a, b| self.compare(i, a, b))
+            .map(|(_, j)| j)
+    }
+
+    /// Finds the `k` nearest neighbors of the `i`-th point in increasing order of distance
+    ///
+    /// Fewer than `k` indices are returned if there are not enough admissible neighbors.
+    pub fn k_nearest(&self, points: &[Vec<f64>], i: usize, k: usize) -> Vec<usize> {
+        let mut candidates = self.candidates(points, i).collect::<Vec<_>>();
+        candidates.sort_by(|a, b| self.compare(i, a, b));
+        candidates.into_iter().take(k).map(|(_, j)| j).collect()
+    }
+
+    /// Distances to the points outside the Theiler window of the `i`-th point
+    fn candidates<'a>(&'a self, points: &'a [Vec<f64>], i: usize) -> impl Iterator<Item = (f64, usize)> + 'a {
+        (0..points.len())
+            .filter(move |&j| i.abs_diff(j) > self.theiler)
+            .map(move |j| (self.metric.distance(&points[i], &points[j]), j))
+            .filter(move |&(d, _)| !d.is_nan() && (!self.skip_zero || d > 0.0))
+    }
+
+    /// Orders candidates by distance and then by the tie-breaking rule
+    fn compare(&self, i: usize, a: &(f64, usize), b: &(f64, usize)) -> Ordering {
+        a.0.total_cmp(&b.0).then_with(|| match self.tie_break {
+            TieBreak::FirstIndex => a.1.cmp(&b.1),
+            TieBreak::LastIndex => b.1.cmp(&a.1),
+            TieBreak::FarthestInTime => i.abs_diff(b.1).cmp(&i.abs_diff(a.1)).then(a.1.cmp(&b.1)),
+        })
+    }
+}
+
+/// Finds the nearest neighbor of the `i`-th embedded point
+///
+/// Ties are resolved in favor of the smallest index.
+pub fn nearest_neighbor<C>(coord: &C, slice: &[f64], i: usize, metric: Metric, theiler: usize) -> Option<usize>
+where
+    C: DelayCoordinates,
+{
+    let search = NeighborSearch {
+        theiler,
+        ..NeighborSearch::new(metric)
+    };
+    search.nearest(&crate::embed(coord, slice), i)
+}
+
+/// Finds the nearest neighbor of every embedded point
+///
+/// The $i$-th element is `nearest_neighbor(coord, slice, i, metric, theiler)`, computed with a
+/// single embedding of the series. It is `None` for the points without a neighbor outside the
+/// Theiler window.
+pub fn all_nearest_neighbors<C>(coord: &C, slice: &[f64], metric: Metric, theiler: usize) -> Vec<Option<usize>>
+where
+    C: DelayCoordinates,
+{
+    let points = crate::embed(coord, slice);
+    let search = NeighborSearch {
+        theiler,
+        ..NeighborSearch::new(metric)
+    };
+    (0..points.len()).map(|i| search.nearest(&points, i)).collect()
+}
+
+/// Ranks the embedded points from the densest to the sparsest region of the state space
+///
+/// The density at a point is estimated by the inverse of the mean distance to its `k` nearest
+/// neighbors, the point itself excluded. The indices of the points are returned in decreasing
+/// order of density, and ties are resolved in favor of the smallest index.
+/// A point with a NaN coordinate has no neighbors, so it is ranked among the sparsest.
+pub fn density_ranking<C>(coord: &C, slice: &[f64], k: usize, metric: Metric) -> Vec<usize>
+where
+    C: DelayCoordinates,
+{
+    let points = crate::embed(coord, slice);
+    let search = NeighborSearch::new(metric);
+    let spread = (0..points.len())
+        .map(|i| {
+            let neighbors = search.k_nearest(&points, i, k);
+            if neighbors.is_empty() {
+                return f64::INFINITY;
+            }
+            neighbors.iter().map(|&j| metric.distance(&points[i], &points[j])).sum::<f64>() / neighbors.len() as f64
+        })
+        .collect::<Vec<f64>>();
+    let mut ranking = (0..points.len()).collect::<Vec<usize>>();
+    ranking.sort_by(|&a, &b| spread[a].total_cmp(&spread[b]).then(a.cmp(&b)));
+    ranking
+}
+
+/// Computes a neighbor-based statistic of determinism against stochasticity
+///
+/// The series is embedded in forward delay-coordinates, and the successor of a point is the
+/// point one window size later, whose window does not overlap with that of the point. Each
+/// successor is predicted by the mean successor of the `k` nearest neighbors of the point
+/// outside a Theiler window of `delay * dimension`. With $E$ the mean squared prediction error
+/// and $V$ the variance of the successors, the statistic is $1 - (E / V) / (1 + 1/k)$, where
+/// $1 + 1/k$ is the expected $E / V$ for independent successors. Like the test of Kaplan and
+/// Glass, it asks whether neighboring states move alike: it is close to 1 for a deterministic
+/// flow and close to 0 for noise.
+/// It is NaN if no point has a neighbor, if the successors do not vary or if `k` is zero.
+/// Returns `Error::DegenerateSeries` if the series is constant.
+pub fn determinism_test(series: &[f64], delay: usize, dimension: usize, k: usize) -> Result<f64, Error> {
+    if is_degenerate(series) {
+        return Err(Error::DegenerateSeries);
+    }
+    let coord = ForwardDelayCoordinates {
+        delay,
+        dimension,
+    };
+    let points = crate::embed(&coord, series);
+    let horizon = coord.window_size();
+    if points.len() <= horizon || k == 0 {
+        return Ok(f64::NAN);
+    }
+    let library = &points[..points.len()-horizon];
+    let successors = &points[horizon..];
+    let search = NeighborSearch {
+        theiler: delay * dimension,
+        ..NeighborSearch::new(Metric::Euclidean)
+    };
+    let squared = |a: &[f64], b: &[f64]| a.iter().zip(b.iter()).map(|(x, y)| (x - y).powi(2)).sum::<f64>();
+    let errors = (0..library.len())
+        .filter_map(|i| {
+            let neighbors = search.k_nearest(library, i, k);
+            if neighbors.is_empty() {
+                return None;
+            }
+            let mut prediction = vec![0.0; dimension];
+            for &j in neighbors.iter() {
+                for (p, x) in prediction.iter_mut().zip(successors[j].iter()) {
+                    *p += x / neighbors.len() as f64;
+                }
+            }
+            Some(squared(&successors[i], &prediction))
+        })
+        .collect::<Vec<f64>>();
+    let mut mean = vec![0.0; dimension];
+    for q in successors.iter() {
+        for (m, x) in mean.iter_mut().zip(q.iter()) {
+            *m += x / successors.len() as f64;
+        }
+    }
+    let variance = successors.iter().map(|q| squared(q, &mean)).sum::<f64>() / successors.len() as f64;
+    if errors.is_empty() || variance <= 0.0 {
+        return Ok(f64::NAN);
+    }
+    let error = errors.iter().sum::<f64>() / errors.len() as f64;
+    Ok(1.0 - error / variance / (1.0 + 1.0 / k as f64))
+}
+
+/// Computes the stretching curves of Kantz's algorithm for the maximal Lyapunov exponent
+///
+/// For each radius $\epsilon$ and each step $\Delta n$ in `0..=max_steps`, the stretching factor
+/// $$S(\epsilon, \Delta n) = \frac{1}{N} \sum_{n_0} \ln \left( \frac{1}{|U(n_0)|}
+/// \sum_{n \in U(n_0)} \| p_{n_0 + \Delta n} - p_{n + \Delta n} \| \right)$$
+/// is averaged over the reference points $n_0$ with a non-empty neighborhood $U(n_0)$ of the
+/// points within $\epsilon$ in the maximum norm and outside the Theiler window. Only points
+/// with `max_steps` successors are used. The $k$-th element of the result is the curve for
+/// `radii[k]`, and the slope of the curves in their common linear region estimates the maximal
+/// Lyapunov exponent per step. Averaging over neighborhoods makes it more robust to noise than
+/// following a single nearest neighbor.
+/// The reference points whose neighbors all follow them exactly at a step, such as repeated
+/// states of a quantized series, are left out of the average at that step, since the logarithm
+/// of their mean distance is $-\infty$. A step is NaN if every reference point is left out.
+/// A curve is empty if no reference point has a neighbor within its radius.
+pub fn kantz<C>(coord: &C, slice: &[f64], radii: &[f64], theiler: usize, max_steps: usize) -> Vec<Vec<f64>>
+where
+    C: DelayCoordinates,
+{
+    let points = crate::embed(coord, slice);
+    let metric = Metric::Maximum;
+    let usable = points.len().saturating_sub(max_steps);
+    let mut sums = vec![vec![0.0; max_steps+1]; radii.len()];
+    let mut counts = vec![vec![0usize; max_steps+1]; radii.len()];
+    let mut references = vec![0usize; radii.len()];
+    for n0 in 0..usable {
+        let distances = (0..usable)
+            .filter(|&n| n0.abs_diff(n) > theiler)
+            .map(|n| (metric.distance(&points[n0], &points[n]), n))
+            .collect::<Vec<_>>();
+        for (k, &radius) in radii.iter().enumerate() {
+            let neighbors = distances.iter()
+                .filter(|&&(d, _)| d <= radius)
+                .map(|&(_, n)| n)
+                .collect::<Vec<usize>>();
+            if neighbors.is_empty() {
+                continue;
+            }
+            references[k] += 1;
+            for step in 0..=max_steps {
+                let mean = neighbors.iter()
+                    .map(|&n| metric.distance(&points[n0+step], &points[n+step]))
+                    .sum::<f64>() / neighbors.len() as f64;
+                if mean > 0.0 {
+                    sums[k][step] += mean.ln();
+                    counts[k][step] += 1;
+                }
+            }
+        }
+    }
+    sums.into_iter()
+        .zip(counts.iter())
+        .zip(references.iter())
+        .map(|((sum, count), &references)| {
+            if references == 0 {
+                Vec::new()
+            } else {
+                sum.iter()
+                    .zip(count.iter())
+                    .map(|(s, &c)| if c == 0 { f64::NAN } else { s / c as f64 })
+                    .collect()
+            }
+        })
+        .collect()
+}
+
+/// Finds the window whose embedded vector is the closest to `query`
+///
+/// Ties are resolved in favor of the earliest window. The windows at a NaN distance, such as
+/// those with a NaN sample, are skipped. Returns `None` if no window is left.
+///
+/// # Panics
+///
+/// Panics if the length of `query` is not the dimension.
+pub fn find_analog<C>(coord: &C, slice: &[f64], query: &[f64], metric: Metric) -> Option<usize>
+where
+    C: DelayCoordinates,
+{
+    assert_eq!(query.len(), coord.dimension(), "query must have the embedding dimension");
+    coord.mapping_iter(slice)
+        .map(|view| metric.distance(&view.to_vec(), query))
+        .enumerate()
+        .filter(|(_, d)| !d.is_nan())
+        .min_by(|a, b| a.1.total_cmp(&b.1).then(a.0.cmp(&b.0)))
+        .map(|(index, _)| index)
+}
+
+/// Lists the pairs of temporally distant windows whose embedded states coincide
+///
+/// The pairs $(i, j)$ with $i < j$, $j - i$ greater than `theiler` and a Euclidean
+/// distance of at most `tol` are returned in lexicographic order. Many such pairs between
+/// different parts of a trajectory suggest that the dimension is too small for the embedding to
+/// be injective, though exact recurrences of a periodic motion are listed as well.
+pub fn injectivity_violations<C>(coord: &C, slice: &[f64], tol: f64, theiler: usize) -> Vec<(usize, usize)>
+where
+    C: DelayCoordinates,
+{
+    let points = crate::embed(coord, slice);
+    let mut pairs = Vec::new();
+    for (i, p) in points.iter().enumerate() {
+        for (j, q) in points.iter().enumerate().skip(i+theiler+1) {
+            if Metric::Euclidean.distance(p, q) <= tol {
+                pairs.push((i, j));
+            }
+        }
+    }
+    pairs
+}
+
+/// Computes the analog-forecast error at each embedded point
+///
+/// The sample `horizon` steps after the last sample of the window of each point is predicted by
+/// the mean of the corresponding samples of its `k` nearest neighbors in the Euclidean metric,
+/// and the absolute error of the prediction is returned. Neighbors whose windows overlap the
+/// window of the point are excluded, i.e. a Theiler window of `window_size - 1`, so that the
+/// error does not just reflect the smoothness of the series.
+/// Only the points whose predicted sample is in `slice` are used, so the $i$-th element belongs
+/// to the $i$-th embedded point and the result is `horizon` elements shorter than the trajectory.
+pub fn pointwise_prediction_error<C>(coord: &C, slice: &[f64], k: usize, horizon: usize) -> Vec<f64>
+where
+    C: DelayCoordinates,
+{
+    let ws = coord.window_size();
+    if slice.len() < ws + horizon {
+        return Vec::new();
+    }
+    let points = crate::embed(coord, &slice[..slice.len()-horizon]);
+    let future = |i: usize| slice[i+ws-1+horizon];
+    let search = NeighborSearch {
+        theiler: ws - 1,
+        ..NeighborSearch::new(Metric::Euclidean)
+    };
+    (0..points.len())
+        .map(|i| {
+            let neighbors = search.k_nearest(&points, i, k);
+            if neighbors.is_empty() {
+                return f64::NAN;
+            }
+            let prediction = neighbors.iter().map(|&j| future(j)).sum::<f64>() / neighbors.len() as f64;
+            (prediction - future(i)).abs()
+        })
+        .collect()
+}
+
+/// Computes the skill of cross-predicting each channel from the embedding of each other channel
+///
+/// Each channel is embedded with forward delay-coordinates. The entry $(i, j)$ is the correlation
+/// between channel $j$ and its estimate from the embedding of channel $i$, where the value at the
+/// last sample of each window is estimated by the mean over the `k` nearest neighbors of the
+/// window in the Euclidean metric. Neighbors whose windows overlap the window are excluded, i.e.
+/// a Theiler window of `window_size - 1`. This is the cross mapping of convergent cross
+/// mapping, so a high skill of $(i, j)$ suggests that channel $j$ drives channel $i$.
+///
+/// # Panics
+///
+/// Panics if the channels have different lengths or `k` is zero.
+pub fn cross_prediction_matrix(channels: &[&[f64]], delay: usize, dimension: usize, k: usize) -> Vec<Vec<f64>> {
+    let len = channels.first().map_or(0, |c| c.len());
+    assert!(channels.iter().all(|c| c.len() == len), "channels must have the same length");
+    assert!(k > 0, "number of neighbors must be positive");
+    let coord = ForwardDelayCoordinates {
+        delay,
+        dimension,
+    };
+    let last = coord.window_size().saturating_sub(1);
+    let search = NeighborSearch {
+        theiler: last,
+        ..NeighborSearch::new(Metric::Euclidean)
+    };
+    channels.iter()
+        .map(|source| {
+            let points = crate::embed(&coord, source);
+            let neighbors = (0..points.len())
+                .map(|i| search.k_nearest(&points, i, k))
+                .collect::<Vec<_>>();
+            channels.iter()
+                .map(|target| {
+                    let actual = (0..points.len()).map(|i| target[i+last]).collect::<Vec<f64>>();
+                    let estimate = neighbors.iter()
+                        .map(|js| js.iter().map(|&j| actual[j]).sum::<f64>() / js.len() as f64)
+                        .collect::<Vec<f64>>();
+                    pearson(&actual, &estimate)
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Pearson correlation coefficient, which is zero if either series is constant
+fn pearson(x: &[f64], y: &[f64]) -> f64 {
+    let n = x.len() as f64;
+    let mx = x.iter().sum::<f64>() / n;
+    let my = y.iter().sum::<f64>() / n;
+    let sxy = x.iter().zip(y.iter()).map(|(a, b)| (a - mx) * (b - my)).sum::<f64>();
+    let sxx = x.iter().map(|a| (a - mx).powi(2)).sum::<f64>();
+    let syy = y.iter().map(|b| (b - my).powi(2)).sum::<f64>();
+    if sxx == 0.0 || syy == 0.0 {
+        0.0
+    } else {
+        sxy / (sxx * syy).sqrt()
+    }
+}
+
+/// Computes the anomaly score of each embedded point
+///
+/// The score of a point is the z-score of the mean distance to its `k` nearest neighbors outside
+/// the Theiler window among all points, so states far from the rest of the trajectory score high.
+/// All scores are zero if the mean distances do not vary.
+pub fn anomaly_scores<C>(coord: &C, slice: &[f64], k: usize, metric: Metric, theiler: usize) -> Vec<f64>
+where
+    C: DelayCoordinates,
+{
+    let points = crate::embed(coord, slice);
+    let search = NeighborSearch {
+        theiler,
+        ..NeighborSearch::new(metric)
+    };
+    let mut stats = RunningStats::new();
+    let mean_distances = (0..points.len())
+        .map(|i| {
+            let neighbors = search.k_nearest(&points, i, k);
+            let d = neighbors.iter().map(|&j| metric.distance(&points[i], &points[j])).sum::<f64>()
+                / neighbors.len().max(1) as f64;
+            stats.push(d);
+            d
+        })
+        .collect::<Vec<f64>>();
+    let std_dev = stats.std_dev();
+    mean_distances.iter()
+        .map(|d| if std_dev > 0.0 { (d - stats.mean()) / std_dev } else { 0.0 })
+        .collect()
+}
+
+/// Computes the mean distance over all pairs of distinct embedded points
+///
+/// This gives a natural scale of the attractor, e.g. for choosing recurrence thresholds.
+/// Returns zero if there are less than two points.
+pub fn mean_pairwise_distance<C>(coord: &C, slice: &[f64], metric: Metric) -> f64
+where
+    C: DelayCoordinates,
+{
+    mean_distance(&crate::embed(coord, slice), metric)
+}
+
+/// Computes the mean pairwise distance over at most `max_points` evenly spaced embedded points
+///
+/// This approximates `mean_pairwise_distance` for long series in $O(\mathrm{max\_points}^2)$.
+pub fn mean_pairwise_distance_sampled<C>(coord: &C, slice: &[f64], metric: Metric, max_points: usize) -> f64
+where
+    C: DelayCoordinates,
+{
+    let points = crate::embed(coord, slice);
+    let step = points.len().div_ceil(max_points.max(1)).max(1);
+    let sampled = points.into_iter().step_by(step).collect::<Vec<_>>();
+    mean_distance(&sampled, metric)
+}
+
+fn mean_distance(points: &[Vec<f64>], metric: Metric) -> f64 {
+    let n = points.len();
+    if n < 2 {
+        return 0.0;
+    }
+    let sum = (0..n)
+        .flat_map(|i| (i+1..n).map(move |j| (i, j)))
+        .map(|(i, j)| metric.distance(&points[i], &points[j]))
+        .sum::<f64>();
+    sum / (n * (n-1) / 2) as f64
+}
+
+/// Computes a nonstationarity score by comparing the state-space distributions of segments
+///
+/// The series is split into `n_segments` segments of equal length and each is embedded
+/// separately. For each pair of segments $a$ and $b$, with $D_{ab}$ the mean Euclidean distance
+/// between their points and $D_{aa}$ the mean distance within a segment, the normalized energy
+/// distance $(2 D_{ab} - D_{aa} - D_{bb}) / 2 D_{ab}$ is 0 if the segments share a distribution
+/// and approaches 1 as they separate. The score is its maximum over the pairs, so it is close to
+/// 0 for a stationary series, up to the sampling fluctuation, and large if the dynamics drift.
+/// Returns NaN if a segment is too short to be embedded.
+///
+/// # Panics
+///
+/// Panics if `n_segments` is less than 2.
+pub fn stationarity_test<C>(series: &[f64], coord: &C, n_segments: usize) -> f64
+where
+    C: DelayCoordinates,
+{
+    assert!(n_segments >= 2, "at least two segments are needed");
+    let len = series.len() / n_segments;
+    let segments = series.chunks_exact(len.max(1))
+        .take(n_segments)
+        .map(|segment| crate::embed(coord, segment))
+        .collect::<Vec<_>>();
+    if segments.len() < n_segments || segments.iter().any(|points| points.len() < 2) {
+        return f64::NAN;
+    }
+    let within = segments.iter()
+        .map(|points| mean_distance(points, Metric::Euclidean))
+        .collect::<Vec<f64>>();
+    let mut score = 0.0f64;
+    for a in 0..n_segments {
+        for b in a+1..n_segments {
+            let cross = segments[a].iter()
+                .flat_map(|p| segments[b].iter().map(move |q| Metric::Euclidean.distance(p, q)))
+                .sum::<f64>() / (segments[a].len() * segments[b].len()) as f64;
+            if cross > 0.0 {
+                score = score.max((2.0 * cross - within[a] - within[b]) / (2.0 * cross));
+            }
+        }
+    }
+    score
+}
+
+/// Finds the lag that best registers two embedded trajectories
+///
+/// For each lag $\tau$ in `-max_lag..=max_lag`, the mean distance between the embedded states
+/// $p_x(i)$ and $p_y(i+\tau)$ over the overlapping indices is computed, and the lag with the
+/// smallest mean is returned. A positive lag means that `y` lags behind `x`.
+pub fn registration_lag<C>(coord: &C, x: &[f64], y: &[f64], metric: Metric, max_lag: usize) -> isize
+where
+    C: DelayCoordinates,
+{
+    let px = crate::embed(coord, x);
+    let py = crate::embed(coord, y);
+    let max_lag = max_lag as isize;
+    (-max_lag..=max_lag)
+        .filter_map(|lag| {
+            let distances = (0..px.len())
+                .filter_map(|i| {
+                    let j = i as isize + lag;
+                    if j >= 0 && (j as usize) < py.len() {
+                        Some(metric.distance(&px[i], &py[j as usize]))
+                    } else {
+                        None
+                    }
+                })
+                .collect::<Vec<f64>>();
+            if distances.is_empty() {
+                None
+            } else {
+                Some((distances.iter().sum::<f64>() / distances.len() as f64, lag))
+            }
+        })
+        .min_by(|a, b| a.0.total_cmp(&b.0).then(a.1.abs().cmp(&b.1.abs())))
+        .map_or(0, |(_, lag)| lag)
+}
+
+/// Grid index for radius queries on an embedded trajectory
+///
+/// The points are hashed into a uniform grid of cubic cells, and a query only visits the cells
+/// overlapping the bounding box of the ball. This works for all metrics in `Metric` because none
+/// of them is smaller than the maximum distance.
+#[derive(Debug, Clone)]
+pub struct BallIndex {
+    points: Vec<Vec<f64>>,
+    metric: Metric,
+    cell_size: f64,
+    cells: HashMap<Vec<i64>, Vec<usize>>,
+}
+
+impl BallIndex {
+    /// Builds the index of the embedded points of `slice`
+    ///
+    /// The cell size is a tenth of the (sampled) mean pairwise distance.
+    pub fn new<C>(coord: &C, slice: &[f64], metric: Metric) -> Self
+    where
+        C: DelayCoordinates,
+    {
+        let scale = mean_pairwise_distance_sampled(coord, slice, Metric::Maximum, 256);
+        let cell_size = if scale > 0.0 { scale / 10.0 } else { 1.0 };
+        Self::with_cell_size(crate::embed(coord, slice), metric, cell_size)
+    }
+
+    /// Builds the index of `points` with the given cell size
+    pub fn with_cell_size(points: Vec<Vec<f64>>, metric: Metric, cell_size: f64) -> Self {
+        let mut cells: HashMap<Vec<i64>, Vec<usize>> = HashMap::new();
+        for (i, p) in points.iter().enumerate() {
+            let key = p.iter().map(|&x| (x / cell_size).floor() as i64).collect();
+            cells.entry(key).or_default().push(i);
+        }
+        BallIndex {
+            points,
+            metric,
+            cell_size,
+            cells,
+        }
+    }
+
+    /// Indexed points
+    #[inline]
+    pub fn points(&self) -> &[Vec<f64>] { &self.points }
+
+    /// Finds the indices of the points within distance `r` from `query` in increasing order
+    pub fn within_radius(&self, query: &[f64], r: f64) -> Vec<usize> {
+        let lower = query.iter().map(|&x| ((x - r) / self.cell_size).floor() as i64).collect::<Vec<_>>();
+        let upper = query.iter().map(|&x| ((x + r) / self.cell_size).floor() as i64).collect::<Vec<_>>();
+        let boxes = lower.iter().zip(upper.iter())
+            .map(|(l, u)| (u - l + 1) as f64)
+            .product::<f64>();
+        let in_box = |key: &[i64]| key.iter().zip(lower.iter().zip(upper.iter())).all(|(k, (l, u))| l <= k && k <= u);
+        let mut found = Vec::new();
+        let mut visit = |indices: &Vec<usize>| {
+            found.extend(indices.iter().filter(|&&i| self.metric.distance(&self.points[i], query) <= r));
+        };
+        if boxes > self.cells.len() as f64 {
+            self.cells.iter().filter(|(key, _)| in_box(key)).for_each(|(_, indices)| visit(indices));
+        } else {
+            let mut key = lower.clone();
+            loop {
+                if let Some(indices) = self.cells.get(&key) {
+                    visit(indices);
+                }
+                let pos = (0..key.len()).find(|&k| key[k] < upper[k]);
+                match pos {
+                    Some(k) => {
+                        key[k] += 1;
+                        key[..k].copy_from_slice(&lower[..k]);
+                    }
+                    None => break,
+                }
+            }
+        }
+        found.sort_unstable();
+        found
+    }
+}
+
+/// Returns the embedded states at the local extrema of a coordinate
+///
+/// A point is returned with its index when coordinate `coord_index` is strictly larger or
+/// strictly smaller than at both the previous and the next points.
+pub fn extrema_states<C>(coord: &C, slice: &[f64], coord_index: usize) -> Vec<(usize, Vec<f64>)>
+where
+    C: DelayCoordinates,
+{
+    let points = crate::embed(coord, slice);
+    (1..points.len().saturating_sub(1))
+        .filter(|&i| {
+            let (prev, x, next) = (points[i-1][coord_index], points[i][coord_index], points[i+1][coord_index]);
+            (x > prev && x > next) || (x < prev && x < next)
+        })
+        .map(|i| (i, points[i].clone()))
+        .collect()
+}
+
+/// Computes the unwrapped phase of a two-dimensional embedding
+///
+/// The phase of a point is its angle around the centroid of the trajectory, measured from the
+/// first coordinate axis towards the second one. Jumps larger than $\pi$ between consecutive
+/// points are unwrapped, so the phase of an oscillation grows steadily.
+///
+/// # Panics
+///
+/// Panics if the dimension is not 2.
+pub fn phase<C>(coord: &C, slice: &[f64]) -> Vec<f64>
+where
+    C: DelayCoordinates,
+{
+    assert_eq!(coord.dimension(), 2, "phase requires a two-dimensional embedding");
+    let points = crate::embed(coord, slice);
+    let n = points.len() as f64;
+    let cx = points.iter().map(|p| p[0]).sum::<f64>() / n;
+    let cy = points.iter().map(|p| p[1]).sum::<f64>() / n;
+    let mut phases = Vec::with_capacity(points.len());
+    let mut prev: Option<f64> = None;
+    for p in points.iter() {
+        let angle = (p[1] - cy).atan2(p[0] - cx);
+        let unwrapped = match prev {
+            Some(prev) => prev + (angle - prev + PI).rem_euclid(2.0 * PI) - PI,
+            None => angle,
+        };
+        phases.push(unwrapped);
+        prev = Some(unwrapped);
+    }
+    phases
+}
+
+/// Computes the instantaneous angular frequency of a two-dimensional embedding
+///
+/// The frequency is the difference of consecutive values of `phase` in radians per step,
+/// so the result is one element shorter than the trajectory.
+///
+/// # Panics
+///
+/// Panics if the dimension is not 2.
+pub fn instantaneous_frequency<C>(coord: &C, slice: &[f64]) -> Vec<f64>
+where
+    C: DelayCoordinates,
+{
+    phase(coord, slice).windows(2).map(|w| w[1] - w[0]).collect()
+}
+
+/// Computes the rotation number of a two-dimensional embedding
+///
+/// The rotation number is the average increment of `phase` per step divided by 2π,
+/// i.e. the mean number of turns around the centroid per step.
+/// It returns 0 if the trajectory has fewer than two points.
+///
+/// # Panics
+///
+/// Panics if the dimension is not 2.
+pub fn rotation_number<C>(coord: &C, slice: &[f64]) -> f64
+where
+    C: DelayCoordinates,
+{
+    let phases = phase(coord, slice);
+    if phases.len() < 2 {
+        return 0.0;
+    }
+    (phases[phases.len()-1] - phases[0]) / ((phases.len() - 1) as f64 * 2.0 * PI)
+}
+
+/// Streaming estimator of a quantile by the P² algorithm of Jain and Chlamtac
+///
+/// The estimator keeps only five markers, so a recurrence threshold can follow the quantile of
+/// the distances of a long-running stream without storing them. Until five samples are pushed,
+/// the quantile of the pushed samples is computed exactly.
+#[derive(Debug, Clone)]
+pub struct StreamingQuantile {
+    p: f64,
+    count: usize,
+    heights: Vec<f64>,
+    positions: [f64; 5],
+    desired: [f64; 5],
+    increments: [f64; 5],
+}
+
+impl StreamingQuantile {
+    /// Creates an estimator of the `p`-quantile
+    ///
+    /// # Panics
+    ///
+    /// Panics if `p` is not in the open interval `(0, 1)`.
+    pub fn new(p: f64) -> Self {
+        assert!(p > 0.0 && p < 1.0, "p must be in (0, 1)");
+        StreamingQuantile {
+            p,
+            count: 0,
+            heights: Vec::with_capacity(5),
+            positions: [1.0, 2.0, 3.0, 4.0, 5.0],
+            desired: [1.0, 1.0 + 2.0 * p, 1.0 + 4.0 * p, 3.0 + 2.0 * p, 5.0],
+            increments: [0.0, p / 2.0, p, (1.0 + p) / 2.0, 1.0],
+        }
+    }
+
+    /// Number of pushed samples
+    #[inline]
+    pub fn count(&self) -> usize { self.count }
+
+    /// Adds a sample to the estimator
+    pub fn push(&mut self, x: f64) {
+        self.count += 1;
+        if self.count <= 5 {
+            self.heights.push(x);
+            if self.count == 5 {
+                self.heights.sort_by(|a, b| a.total_cmp(b));
+            }
+            return;
+        }
+        let q = &mut self.heights;
+        let k = if x < q[0] {
+            q[0] = x;
+            0
+        } else if x >= q[4] {
+            q[4] = x;
+            3
+        } else {
+            (0..4).find(|&i| x < q[i+1]).unwrap()
+        };
+        for n in self.positions[k+1..].iter_mut() {
+            *n += 1.0;
+        }
+        for (n, dn) in self.desired.iter_mut().zip(self.increments.iter()) {
+            *n += dn;
+        }
+        for i in 1..4 {
+            let n = &mut self.positions;
+            let d = self.desired[i] - n[i];
+            if (d >= 1.0 && n[i+1] - n[i] > 1.0) || (d <= -1.0 && n[i-1] - n[i] < -1.0) {
+                let d = d.signum();
+                let parabolic = q[i] + d / (n[i+1] - n[i-1])
+                    * ((n[i] - n[i-1] + d) * (q[i+1] - q[i]) / (n[i+1] - n[i])
+                       + (n[i+1] - n[i] - d) * (q[i] - q[i-1]) / (n[i] - n[i-1]));
+                q[i] = if q[i-1] < parabolic && parabolic < q[i+1] {
+                    parabolic
+                } else {
+                    let j = if d > 0.0 { i + 1 } else { i - 1 };
+                    q[i] + d * (q[j] - q[i]) / (n[j] - n[i])
+                };
+                n[i] += d;
+            }
+        }
+    }
+
+    /// Current estimate of the quantile, or `None` if no sample is pushed
+    pub fn quantile(&self) -> Option<f64> {
+        match self.count {
+            0 => None,
+            1..=4 => {
+                let mut sorted = self.heights.clone();
+                sorted.sort_by(|a, b| a.total_cmp(b));
+                let index = ((sorted.len() - 1) as f64 * self.p).round() as usize;
+                Some(sorted[index])
+            }
+            _ => Some(self.heights[2]),
+        }
+    }
+}
+
+/// Computes the arc length of the embedded trajectory
+///
+/// The arc length is the sum of the distances between consecutive embedded points.
+pub fn trajectory_length<C>(coord: &C, slice: &[f64], metric: Metric) -> f64
+where
+    C: DelayCoordinates,
+{
+    crate::embed(coord, slice)
+        .windows(2)
+        .map(|w| metric.distance(&w[0], &w[1]))
+        .sum()
+}
+
+/// Computes the variance of each embedded coordinate over all windows
+///
+/// The $i$-th element is the population variance of the $i$-th coordinate.
+pub fn coordinate_variances<C>(coord: &C, slice: &[f64]) -> Vec<f64>
+where
+    C: DelayCoordinates,
+{
+    let mut stats = vec![RunningStats::new(); coord.dimension()];
+    for view in coord.mapping_iter(slice) {
+        for (s, &x) in stats.iter_mut().zip(view.iter()) {
+            s.push(x);
+        }
+    }
+    stats.iter().map(RunningStats::variance).collect()
+}
+
+/// Index of the embedded coordinate with the largest variance
+///
+/// The coordinates with a NaN variance, which have a NaN sample, are skipped. Returns `None` if
+/// no coordinate is left.
+pub fn max_variance_coordinate<C>(coord: &C, slice: &[f64]) -> Option<usize>
+where
+    C: DelayCoordinates,
+{
+    coordinate_variances(coord, slice)
+        .iter()
+        .enumerate()
+        .filter(|(_, v)| !v.is_nan())
+        .max_by(|a, b| a.1.total_cmp(b.1))
+        .map(|(index, _)| index)
+}
+
+/// Plug-in Shannon entropy in nats of the empirical distribution of the symbols
+fn plugin_entropy<K, I>(symbols: I) -> f64
+where
+    K: std::hash::Hash + Eq,
+    I: Iterator<Item = K>,
+{
+    let mut counts: HashMap<K, usize> = HashMap::new();
+    let mut total = 0;
+    for symbol in symbols {
+        *counts.entry(symbol).or_insert(0) += 1;
+        total += 1;
+    }
+    counts.values()
+        .map(|&count| {
+            let p = count as f64 / total as f64;
+            -p * p.ln()
+        })
+        .sum()
+}
+
+/// Estimates the entropy rate of the series from block entropies
+///
+/// The series is discretized into `bins` equal-width bins and the entropy $H_m$ of the words of
+/// $m$ consecutive symbols is estimated by the plug-in estimator. The entropy rate in nats is
+/// estimated as $H_m - H_{m-1}$ with $m$ = `max_m` and $H_0 = 0$.
+///
+/// The plug-in estimator is biased downward when the $\text{bins}^m$ possible words are not
+/// well sampled by the series, so `max_m` should be kept small enough that the series is much
+/// longer than the number of words. Otherwise the estimate tends to zero even for noise.
+///
+/// # Panics
+///
+/// Panics if `max_m` is zero.
+pub fn entropy_rate(series: &[f64], max_m: usize, bins: usize) -> f64 {
+    assert!(max_m > 0, "block length must be positive");
+    let symbols = crate::preprocess::discretize(series, bins);
+    if symbols.len() < max_m {
+        return 0.0;
+    }
+    let block_entropy = |m: usize| if m == 0 { 0.0 } else { plugin_entropy(symbols.windows(m)) };
+    block_entropy(max_m) - block_entropy(max_m - 1)
+}
+
+/// Pairs the past word of each time $t$ with the symbol at $t + 1$
+///
+/// The past word is $(s_t, s_{t-m}, \ldots, s_{t-(d-1)m})$ for the delay $m$ and the
+/// dimension $d$. Only the times with a full past and a next symbol are used, in increasing order.
+fn past_and_next(symbols: &[usize], delay: usize, dimension: usize) -> Vec<(Vec<usize>, usize)> {
+    let coord = ForwardDelayCoordinates {
+        delay,
+        dimension,
+    };
+    let ws = coord.window_size();
+    if ws == 0 || symbols.len() <= ws {
+        return Vec::new();
+    }
+    coord.mapping_iter(&symbols[..symbols.len()-1])
+        .zip(symbols[ws..].iter())
+        .map(|(view, &next)| (view.to_vec(), next))
+        .collect()
+}
+
+/// Estimates the active information storage of the series
+///
+/// The series is discretized into `bins` equal-width bins and the active information storage
+/// is the mutual information in nats between the past word, embedded in forward
+/// delay-coordinates with the given delay and dimension, and the next symbol. It is high when
+/// the past predicts the next value and near zero for noise, up to the upward bias of the
+/// plug-in estimator of about $\text{bins}^{d+1} / 2N$ for $N$ samples.
+/// It is 0 if the series is too short to have a past and a next value.
+pub fn active_information_storage(series: &[f64], delay: usize, dimension: usize, bins: usize) -> f64 {
+    let symbols = crate::preprocess::discretize(series, bins);
+    let pairs = past_and_next(&symbols, delay, dimension);
+    if pairs.is_empty() {
+        return 0.0;
+    }
+    plugin_entropy(pairs.iter().map(|(past, _)| past))
+        + plugin_entropy(pairs.iter().map(|(_, next)| next))
+        - plugin_entropy(pairs.iter())
+}
+
+/// Estimates the transfer entropy from `source` to `target`
+///
+/// Both series are discretized into `bins` equal-width bins and their past words are embedded
+/// in forward delay-coordinates with the given delay and dimension. The transfer entropy is the
+/// conditional mutual information in nats between the past of the source and the next symbol of
+/// the target given the past of the target, so it measures what the source tells about the
+/// future of the target beyond its own past. It is not symmetric, and the direction with the
+/// larger value suggests the direction of the coupling.
+/// The series are truncated to the shorter one, and the result is 0 if it is too short.
+pub fn transfer_entropy(source: &[f64], target: &[f64], delay: usize, dimension: usize, bins: usize) -> f64 {
+    let len = source.len().min(target.len());
+    let x = past_and_next(&crate::preprocess::discretize(&source[..len], bins), delay, dimension);
+    let y = past_and_next(&crate::preprocess::discretize(&target[..len], bins), delay, dimension);
+    if y.is_empty() {
+        return 0.0;
+    }
+    let joint = y.iter()
+        .zip(x.iter())
+        .map(|((y_past, y_next), (x_past, _))| (y_past, *y_next, x_past))
+        .collect::<Vec<_>>();
+    plugin_entropy(joint.iter().map(|&(y_past, y_next, _)| (y_past, y_next)))
+        + plugin_entropy(joint.iter().map(|&(y_past, _, x_past)| (y_past, x_past)))
+        - plugin_entropy(joint.iter().map(|&(y_past, _, _)| y_past))
+        - plugin_entropy(joint.iter())
+}
+
+/// Computes the Higuchi fractal dimension of the series
+///
+/// For each $k$ in `1..=k_max` and each offset $m < k$, the normalized length of the
+/// subsampled curve $x(m), x(m+k), x(m+2k), \ldots$ is averaged into $L(k)$, and the fractal
+/// dimension is the least-squares slope of $\ln L(k)$ against $\ln(1/k)$. It is about 1 for a
+/// smooth curve and about 2 for white noise.
+/// It is NaN if `k_max` is less than 2 or the series is too short for the subsampled curves.
+/// Returns `Error::DegenerateSeries` if the series is constant.
+pub fn higuchi_fd(series: &[f64], k_max: usize) -> Result<f64, Error> {
+    if is_degenerate(series) {
+        return Err(Error::DegenerateSeries);
+    }
+    let n = series.len();
+    let (mut log_k, mut log_l) = (Vec::new(), Vec::new());
+    for k in 1..=k_max {
+        let lengths = (0..k)
+            .filter_map(|m| {
+                let steps = n.checked_sub(m+1)? / k;
+                if steps == 0 {
+                    return None;
+                }
+                let sum = (1..=steps)
+                    .map(|i| (series[m+i*k] - series[m+(i-1)*k]).abs())
+                    .sum::<f64>();
+                Some(sum * (n - 1) as f64 / (steps * k) as f64 / k as f64)
+            })
+            .collect::<Vec<f64>>();
+        if lengths.len() < k {
+            break;
+        }
+        log_k.push(-(k as f64).ln());
+        log_l.push((lengths.iter().sum::<f64>() / k as f64).ln());
+    }
+    if log_k.len() < 2 {
+        return Ok(f64::NAN);
+    }
+    Ok(fit_slope(&log_k, &log_l))
+}
+
+/// Computes the sample entropy of the series
+///
+/// With $B$ the number of pairs of templates of length `m` and $A$ the number of pairs of
+/// templates of length `m + 1` within the tolerance `r` in the maximum norm, over the same
+/// $N - m$ starting points and excluding self-matches, the sample entropy is $-\ln(A / B)$.
+/// `r` is an absolute tolerance, commonly 0.1 to 0.25 times the standard deviation.
+/// It is infinity if no template of length `m + 1` matches and NaN if none of length `m` does.
+/// Returns `Error::DegenerateSeries` if the series is constant.
+pub fn sample_entropy(series: &[f64], m: usize, r: f64) -> Result<f64, Error> {
+    if is_degenerate(series) {
+        return Err(Error::DegenerateSeries);
+    }
+    Ok(sample_entropy_unchecked(series, m, r))
+}
+
+/// Sample entropy of a series that may be constant
+fn sample_entropy_unchecked(series: &[f64], m: usize, r: f64) -> f64 {
+    let n = series.len();
+    if n <= m {
+        return f64::NAN;
+    }
+    let (mut a, mut b) = (0usize, 0usize);
+    for i in 0..n-m {
+        for j in i+1..n-m {
+            if (0..m).all(|k| (series[i+k] - series[j+k]).abs() <= r) {
+                b += 1;
+                if (series[i+m] - series[j+m]).abs() <= r {
+                    a += 1;
+                }
+            }
+        }
+    }
+    if b == 0 {
+        f64::NAN
+    } else {
+        -(a as f64 / b as f64).ln()
+    }
+}
+
+/// Computes the multiscale entropy of the series
+///
+/// For each scale $s$ in `scales`, the series is coarse-grained into the means of consecutive
+/// non-overlapping blocks of $s$ samples and the sample entropy of the coarse-grained series is
+/// computed with the template length `m` and the tolerance `r`. The tolerance is not rescaled,
+/// so it is usually set from the standard deviation of the original series.
+/// Returns `Error::DegenerateSeries` if the series is constant.
+///
+/// # Panics
+///
+/// Panics if a scale is zero.
+pub fn multiscale_entropy(series: &[f64], scales: &[usize], m: usize, r: f64) -> Result<Vec<f64>, Error> {
+    if is_degenerate(series) {
+        return Err(Error::DegenerateSeries);
+    }
+    let entropies = scales.iter()
+        .map(|&scale| {
+            assert!(scale > 0, "scale must be positive");
+            let coarse = series.chunks_exact(scale)
+                .map(|block| block.iter().sum::<f64>() / scale as f64)
+                .collect::<Vec<f64>>();
+            sample_entropy_unchecked(&coarse, m, r)
+        })
+        .collect();
+    Ok(entropies)
+}
+
+/// Computes the normalized spectral entropy of each window
+///
+/// The whole window of each embedded point, including the samples between the coordinates, is
+/// centered and its power spectrum is computed at the frequencies $k/n$ for
+/// $k = 1, \ldots, \lfloor n/2 \rfloor$. The Shannon entropy of the normalized spectrum divided
+/// by the logarithm of the number of frequencies is close to 0 for a pure tone and to 1 for white
+/// noise. It is 0 for a window without variation or with fewer than four samples.
+/// With the `fft` feature, the spectrum of a window whose size is a power of two is computed by
+/// a radix-2 FFT. Otherwise a direct DFT is used, which takes quadratic time in the window size.
+pub fn spectral_entropy<C>(coord: &C, slice: &[f64]) -> Vec<f64>
+where
+    C: DelayCoordinates,
+{
+    coord.mapping_iter(slice)
+        .map(|view| {
+            let window = view.window_slice();
+            let n = window.len();
+            if n < 4 {
+                return 0.0;
+            }
+            let mean = window.iter().sum::<f64>() / n as f64;
+            let centered = window.iter().map(|x| x - mean).collect::<Vec<f64>>();
+            let power = power_spectrum(&centered);
+            let total = power.iter().sum::<f64>();
+            if total <= 0.0 {
+                return 0.0;
+            }
+            let entropy = power.iter()
+                .filter(|&&p| p > 0.0)
+                .map(|&p| -(p / total) * (p / total).ln())
+                .sum::<f64>();
+            entropy / (power.len() as f64).ln()
+        })
+        .collect()
+}
+
+/// Power of `x` at the frequencies $k/n$ for $k = 1, \ldots, \lfloor n/2 \rfloor$
+fn power_spectrum(x: &[f64]) -> Vec<f64> {
+    let n = x.len();
+    #[cfg(feature = "fft")]
+    {
+        if n.is_power_of_two() {
+            let mut data = x.iter().map(|&x| (x, 0.0)).collect::<Vec<_>>();
+            fft::fft(&mut data);
+            return data[1..=n/2].iter().map(|(re, im)| re * re + im * im).collect();
+        }
+    }
+    (1..=n/2)
+        .map(|k| {
+            let (re, im) = x.iter()
+                .enumerate()
+                .fold((0.0, 0.0), |(re, im), (t, x)| {
+                    let angle = 2.0 * PI * (k * t) as f64 / n as f64;
+                    (re + x * angle.cos(), im - x * angle.sin())
+                });
+            re * re + im * im
+        })
+        .collect()
+}
+
+/// Covariance matrix of the coordinates of a set of points
+///
+/// The covariances are normalized by the number of points.
+pub(crate) fn covariance_matrix(points: &[Vec<f64>]) -> Vec<Vec<f64>> {
+    let dim = points.first().map_or(0, |p| p.len());
+    let n = points.len() as f64;
+    let mut mean = vec![0.0; dim];
+    for p in points.iter() {
+        for (m, x) in mean.iter_mut().zip(p.iter()) {
+            *m += x;
+        }
+    }
+    for m in mean.iter_mut() {
+        *m /= n;
+    }
+    let mut cov = vec![vec![0.0; dim]; dim];
+    for p in points.iter() {
+        for (row, (xi, mi)) in cov.iter_mut().zip(p.iter().zip(mean.iter())) {
+            for (c, (xj, mj)) in row.iter_mut().zip(p.iter().zip(mean.iter())) {
+                *c += (xi - mi) * (xj - mj) / n;
+            }
+        }
+    }
+    cov
+}
+
+/// Singular spectrum of the trajectory matrix $X$ of the embedded series
+///
+/// Returns the eigenvalues of $X^T X$, the squared singular values, in decreasing order and the
+/// corresponding right singular vectors.
+fn ssa_spectrum(points: &[Vec<f64>]) -> (Vec<f64>, Vec<Vec<f64>>) {
+    let dim = points.first().map_or(0, |p| p.len());
+    let mut gram = vec![vec![0.0; dim]; dim];
+    for p in points.iter() {
+        for (row, xi) in gram.iter_mut().zip(p.iter()) {
+            for (g, xj) in row.iter_mut().zip(p.iter()) {
+                *g += xi * xj;
+            }
+        }
+    }
+    symmetric_eigen(gram)
+}
+
+/// Cumulative fraction of the variance explained by the leading SSA components
+///
+/// The $k$-th element is the share of the squared singular values of the trajectory matrix
+/// carried by the components `0..=k`, sorted in decreasing order, so the last one is 1.
+/// All elements are 0 if the trajectory matrix is zero.
+pub fn ssa_cumulative_variance<C>(coord: &C, slice: &[f64]) -> Vec<f64>
+where
+    C: DelayCoordinates,
+{
+    let (values, _) = ssa_spectrum(&crate::embed(coord, slice));
+    let total = values.iter().sum::<f64>();
+    values.iter()
+        .scan(0.0, |sum, value| {
+            *sum += value;
+            Some(if total > 0.0 { *sum / total } else { 0.0 })
+        })
+        .collect()
+}
+
+/// Reconstructs the series from a subset of its SSA components
+///
+/// The trajectory matrix $X$ is projected onto the right singular vectors $v_i$ of the chosen
+/// components, numbered in decreasing order of the singular values, as
+/// $\sum_i X v_i v_i^T$. Each element of the projected matrix is mapped back to the sample it
+/// was read from and the elements of each sample are averaged (diagonal averaging), so the
+/// reconstruction with all components is the original series.
+/// The samples that no coordinate reads are NaN.
+///
+/// # Panics
+///
+/// Panics if a component is not less than the dimension.
+pub fn ssa_reconstruct<C>(coord: &C, slice: &[f64], components: &[usize]) -> Vec<f64>
+where
+    C: DelayCoordinates,
+{
+    let points = crate::embed(coord, slice);
+    let (_, vectors) = ssa_spectrum(&points);
+    let offsets = coord.map_coords();
+    let mut sums = vec![0.0; slice.len()];
+    let mut counts = vec![0usize; slice.len()];
+    for (start, p) in points.iter().enumerate() {
+        let mut projected = vec![0.0; p.len()];
+        for &component in components.iter() {
+            let v = &vectors[component];
+            let score = p.iter().zip(v.iter()).map(|(x, y)| x * y).sum::<f64>();
+            for (x, y) in projected.iter_mut().zip(v.iter()) {
+                *x += score * y;
+            }
+        }
+        for (x, offset) in projected.iter().zip(offsets.iter()) {
+            sums[start+offset] += x;
+            counts[start+offset] += 1;
+        }
+    }
+    sums.iter()
+        .zip(counts.iter())
+        .map(|(&sum, &count)| if count == 0 { f64::NAN } else { sum / count as f64 })
+        .collect()
+}
+
+/// Eigenvalues and modes of a dynamic mode decomposition
+#[derive(Debug, Clone, PartialEq)]
+pub struct HankelDmd {
+    /// Eigenvalues as pairs of real and imaginary parts in decreasing order of modulus
+    pub eigenvalues: Vec<(f64, f64)>,
+    /// Mode of each eigenvalue over the delay-coordinates
+    pub modes: Vec<Vec<(f64, f64)>>,
+}
+
+/// Computes the dynamic mode decomposition of the embedded series (Hankel-DMD)
+///
+/// The embedded vectors are the snapshots, and the linear map advancing each of them to the
+/// next window is fitted on the leading `rank` left singular vectors of the snapshots. The
+/// eigenvalues of the reduced map are returned as pairs of real and imaginary parts in
+/// decreasing order of modulus, each with its DMD mode. A mode is the eigenvector of the reduced
+/// map projected back onto the delay-coordinates, normalized to unit length with an arbitrary
+/// phase.
+/// An eigenvalue $\lambda$ of a mode oscillating at $\omega$ radians per sample and decaying at
+/// the rate $\gamma$ is $e^{-\gamma \pm i\omega}$, so $\omega = \arg \lambda$ and
+/// $\gamma = -\ln |\lambda|$.
+/// The rank is truncated to the dimension and to the singular values that are not zero. The
+/// eigenvalue solver is meant for small ranks.
+pub fn hankel_dmd<C>(coord: &C, slice: &[f64], rank: usize) -> HankelDmd
+where
+    C: DelayCoordinates,
+{
+    let points = crate::embed(coord, slice);
+    if points.len() < 2 {
+        return HankelDmd { eigenvalues: Vec::new(), modes: Vec::new() };
+    }
+    let dim = coord.dimension();
+    let (snapshots, shifted) = (&points[..points.len()-1], &points[1..]);
+    let mut gram = vec![vec![0.0; dim]; dim];
+    let mut cross = vec![vec![0.0; dim]; dim];
+    for (x, y) in snapshots.iter().zip(shifted.iter()) {
+        for ((g, c), (xi, yi)) in gram.iter_mut().zip(cross.iter_mut()).zip(x.iter().zip(y.iter())) {
+            for ((g, c), xj) in g.iter_mut().zip(c.iter_mut()).zip(x.iter()) {
+                *g += xi * xj;
+                *c += yi * xj;
+            }
+        }
+    }
+    let (values, vectors) = symmetric_eigen(gram);
+    let largest = values.first().cloned().unwrap_or(0.0);
+    let rank = values.iter()
+        .take(rank)
+        .take_while(|&&value| value > 1e-12 * largest)
+        .count();
+    let dot = |a: &[f64], b: &[f64]| a.iter().zip(b.iter()).map(|(x, y)| x * y).sum::<f64>();
+    let reduced = (0..rank)
+        .map(|i| {
+            (0..rank)
+                .map(|j| {
+                    let mapped = cross.iter().map(|row| dot(row, &vectors[j])).collect::<Vec<f64>>();
+                    dot(&vectors[i], &mapped) / values[j]
+                })
+                .collect()
+        })
+        .collect::<Vec<Vec<f64>>>();
+    let mut eigenvalues = crate::linalg::eigenvalues(&reduced);
+    eigenvalues.sort_by(|a, b| b.0.hypot(b.1).total_cmp(&a.0.hypot(a.1)).then(b.1.total_cmp(&a.1)));
+    let modes = eigenvalues.iter()
+        .map(|&value| {
+            let w = crate::linalg::eigenvector(&reduced, value);
+            (0..dim)
+                .map(|i| {
+                    w.iter()
+                        .zip(vectors.iter())
+                        .fold((0.0, 0.0), |acc, (wj, u)| (acc.0 + u[i] * wj.0, acc.1 + u[i] * wj.1))
+                })
+                .collect()
+        })
+        .collect();
+    HankelDmd { eigenvalues, modes }
+}
+
+/// Effective dimension of the embedded series by the participation ratio of its PCA eigenvalues
+///
+/// The participation ratio $(\sum_i \lambda_i)^2 / \sum_i \lambda_i^2$ of the eigenvalues of
+/// the covariance matrix is 1 if the variance lies on one principal axis and the dimension if it
+/// is spread evenly. It is computed from the trace of the covariance matrix and of its square,
+/// which equal the sums, so the eigenvalues are not computed explicitly.
+/// It is 0 if the embedded series has no variance.
+/// Returns `Error::DegenerateSeries` if the series is constant.
+pub fn effective_dimension<C>(coord: &C, slice: &[f64]) -> Result<f64, Error>
+where
+    C: DelayCoordinates,
+{
+    if is_degenerate(slice) {
+        return Err(Error::DegenerateSeries);
+    }
+    let cov = covariance_matrix(&crate::embed(coord, slice));
+    let trace = (0..cov.len()).map(|i| cov[i][i]).sum::<f64>();
+    let squares = cov.iter().flatten().map(|c| c * c).sum::<f64>();
+    if squares == 0.0 {
+        return Ok(0.0);
+    }
+    Ok(trace * trace / squares)
+}
+
+/// Projects the embedded series onto its top `out_dim` principal components
+///
+/// The embedded vectors are centered by their mean, and the $k$-th coordinate of each projected
+/// vector is its component along the eigenvector of the covariance matrix with the $k$-th
+/// largest eigenvalue. The projection is a rotation if `out_dim` equals the dimension, so it
+/// keeps the distances, and otherwise the view of the trajectory with the largest variance.
+/// `out_dim` is clipped to the dimension.
+pub fn project<C>(coord: &C, slice: &[f64], out_dim: usize) -> Vec<Vec<f64>>
+where
+    C: DelayCoordinates,
+{
+    let points = crate::embed(coord, slice);
+    if points.is_empty() {
+        return Vec::new();
+    }
+    let n = points.len() as f64;
+    let mean = (0..coord.dimension())
+        .map(|i| points.iter().map(|p| p[i]).sum::<f64>() / n)
+        .collect::<Vec<f64>>();
+    let (_, vectors) = symmetric_eigen(covariance_matrix(&points));
+    points.iter()
+        .map(|p| {
+            vectors.iter()
+                .take(out_dim)
+                .map(|v| v.iter().zip(p.iter().zip(mean.iter())).map(|(a, (x, m))| a * (x - m)).sum())
+                .collect()
+        })
+        .collect()
+}
+
+/// Lag at which the embedded states decorrelate
+///
+/// The mean distance $D(\tau)$ between the states $p_t$ and $p_{t+\tau}$ grows from zero with
+/// the lag until it levels off at the size of the attractor, the mean distance over all pairs
+/// of states. The returned lag is the smallest one at which $D(\tau)$ reaches $1 - 1/e$ of the
+/// size. For a periodic series it is a fixed fraction of the period.
+/// The number of embedded points is returned if no lag reaches it.
+/// Returns `Error::DegenerateSeries` if the series is constant.
+pub fn state_decorrelation_time<C>(coord: &C, slice: &[f64], metric: Metric) -> Result<usize, Error>
+where
+    C: DelayCoordinates,
+{
+    if is_degenerate(slice) {
+        return Err(Error::DegenerateSeries);
+    }
+    let points = crate::embed(coord, slice);
+    let n = points.len();
+    let mut pairs = 0usize;
+    let mut sum = 0.0;
+    for (i, p) in points.iter().enumerate() {
+        for q in points[i+1..].iter() {
+            sum += metric.distance(p, q);
+            pairs += 1;
+        }
+    }
+    if sum == 0.0 {
+        return Ok(n);
+    }
+    let level = (1.0 - (-1.0f64).exp()) * sum / pairs as f64;
+    let lag = (1..n)
+        .find(|&lag| {
+            let total = points.iter()
+                .zip(points[lag..].iter())
+                .map(|(p, q)| metric.distance(p, q))
+                .sum::<f64>();
+            total / (n - lag) as f64 >= level
+        })
+        .unwrap_or(n);
+    Ok(lag)
+}
+
+/// Estimates the correlation dimension with a bootstrap confidence interval
+///
+/// The estimate is the least-squares slope of $\ln C(r)$ against $\ln r$ over `radii`, where
+/// the correlation sum counts the pairs of distinct points within the Euclidean distance $r$.
+/// The slope is recomputed on `n_bootstrap` resamples of the embedded points drawn with
+/// replacement, where copies of the same point are not paired, and the lower and upper bounds
+/// are the 2.5th and 97.5th percentiles of the slopes. The resampling uses a fixed seed, so the
+/// result is reproducible.
+/// The returned triple is (estimate, lower, upper). The bounds are the estimate itself if
+/// `n_bootstrap` is zero, and all three are NaN if a correlation sum at one of `radii` is zero.
+/// Returns `Error::DegenerateSeries` if the series is constant.
+pub fn correlation_dimension_ci<C>(coord: &C, slice: &[f64], radii: &[f64], n_bootstrap: usize)
+    -> Result<(f64, f64, f64), Error>
+where
+    C: DelayCoordinates,
+{
+    if is_degenerate(slice) {
+        return Err(Error::DegenerateSeries);
+    }
+    let points = crate::embed(coord, slice);
+    let log_r = radii.iter().map(|r| r.ln()).collect::<Vec<f64>>();
+    let slope = |indices: &[usize]| {
+        let mut counts = vec![0usize; radii.len()];
+        let mut pairs = 0usize;
+        for (a, &i) in indices.iter().enumerate() {
+            for &j in indices[a+1..].iter().filter(|&&j| j != i) {
+                let d = Metric::Euclidean.distance(&points[i], &points[j]);
+                pairs += 1;
+                for (count, &r) in counts.iter_mut().zip(radii.iter()) {
+                    if d <= r {
+                        *count += 1;
+                    }
+                }
+            }
+        }
+        let log_c = counts.iter().map(|&c| (c as f64 / pairs as f64).ln()).collect::<Vec<f64>>();
+        let slope = fit_slope(&log_r, &log_c);
+        if slope.is_finite() { slope } else { f64::NAN }
+    };
+    let estimate = slope(&(0..points.len()).collect::<Vec<usize>>());
+    if estimate.is_nan() {
+        return Ok((f64::NAN, f64::NAN, f64::NAN));
+    }
+    let mut rng = Xorshift::new(points.len() as u64);
+    let mut slopes = (0..n_bootstrap)
+        .map(|_| slope(&(0..points.len()).map(|_| rng.below(points.len())).collect::<Vec<usize>>()))
+        .filter(|s| !s.is_nan())
+        .collect::<Vec<f64>>();
+    if slopes.is_empty() {
+        return Ok((estimate, estimate, estimate));
+    }
+    slopes.sort_by(|a, b| a.total_cmp(b));
+    let percentile = |p: f64| slopes[(p * (slopes.len() - 1) as f64).round() as usize];
+    Ok((estimate, percentile(0.025), percentile(0.975)))
+}
+
+/// Estimates the generalized (Rényi) dimensions of the embedded series
+///
+/// For each $q$, the dimension $D_q$ is the least-squares slope over `radii` of
+/// $\ln C_q(r) / (q - 1)$ against $\ln r$, where
+/// $$C_q(r) = \frac{1}{N} \sum_i p_i(r)^{q-1}$$
+/// and $p_i(r)$ is the fraction of the other points within the Euclidean distance $r$ of the
+/// $i$-th point. For $q = 1$ the limit $\frac{1}{N} \sum_i \ln p_i(r)$ is used instead, and
+/// $q = 2$ gives the correlation dimension. The points with no other point within a radius are
+/// left out at that radius. $D_q$ does not increase with $q$, and it is constant for a
+/// monofractal set.
+/// $D_q$ is NaN if no point has a neighbor within one of `radii`.
+/// Returns `Error::DegenerateSeries` if the series is constant.
+pub fn renyi_dimensions<C>(coord: &C, slice: &[f64], q_values: &[f64], radii: &[f64]) -> Result<Vec<f64>, Error>
+where
+    C: DelayCoordinates,
+{
+    if is_degenerate(slice) {
+        return Err(Error::DegenerateSeries);
+    }
+    let points = crate::embed(coord, slice);
+    let n = points.len();
+    let mut counts = vec![vec![0usize; radii.len()]; n];
+    for (i, p) in points.iter().enumerate() {
+        for (j, q) in points.iter().enumerate().skip(i+1) {
+            let d = Metric::Euclidean.distance(p, q);
+            for (k, &r) in radii.iter().enumerate() {
+                if d <= r {
+                    counts[i][k] += 1;
+                    counts[j][k] += 1;
+                }
+            }
+        }
+    }
+    let log_r = radii.iter().map(|r| r.ln()).collect::<Vec<f64>>();
+    let dimensions = q_values.iter()
+        .map(|&q| {
+            let log_c = (0..radii.len())
+                .map(|k| {
+                    let fractions = counts.iter()
+                        .filter(|c| c[k] > 0)
+                        .map(|c| c[k] as f64 / (n - 1) as f64)
+                        .collect::<Vec<f64>>();
+                    let m = fractions.len() as f64;
+                    if q == 1.0 {
+                        fractions.iter().map(|p| p.ln()).sum::<f64>() / m
+                    } else {
+                        (fractions.iter().map(|p| p.powf(q - 1.0)).sum::<f64>() / m).ln() / (q - 1.0)
+                    }
+                })
+                .collect::<Vec<f64>>();
+            let slope = fit_slope(&log_r, &log_c);
+            if slope.is_finite() { slope } else { f64::NAN }
+        })
+        .collect();
+    Ok(dimensions)
+}
+
+/// Selects `target_points` evenly spaced windows of the embedded series
+///
+/// The first and the last windows are always selected when `target_points` is at least two.
+/// If there are fewer windows than `target_points`, some windows are repeated, so the result has
+/// exactly `target_points` points unless the trajectory is empty.
+pub fn resample_trajectory<C>(coord: &C, slice: &[f64], target_points: usize) -> Vec<Vec<f64>>
+where
+    C: DelayCoordinates,
+{
+    let points = crate::embed(coord, slice);
+    if points.is_empty() {
+        return Vec::new();
+    }
+    let last = (points.len() - 1) as f64;
+    let scale = if target_points > 1 { last / (target_points - 1) as f64 } else { 0.0 };
+    (0..target_points)
+        .map(|k| points[(k as f64 * scale).round() as usize].clone())
+        .collect()
+}
+
+/// Smooths the embedded trajectory with an exponential moving average
+///
+/// The smoothed points are $s_0 = p_0$ and $s_k = \alpha p_k + (1 - \alpha) s_{k-1}$,
+/// so smaller `alpha` gives stronger smoothing.
+pub fn smoothed_trajectory<C>(coord: &C, slice: &[f64], alpha: f64) -> Vec<Vec<f64>>
+where
+    C: DelayCoordinates,
+{
+    let mut points = crate::embed(coord, slice);
+    for k in 1..points.len() {
+        let (prev, rest) = points.split_at_mut(k);
+        for (s, &p) in rest[0].iter_mut().zip(prev[k-1].iter()) {
+            *s = alpha * *s + (1.0 - alpha) * p;
+        }
+    }
+    points
+}
+
+/// Computes the autocorrelation of a series for the lags `0..=max_lag`
+///
+/// The autocorrelation is normalized by the variance, so the value at lag 0 is 1.
+/// All values are zero for a constant series.
+pub fn autocorrelation(series: &[f64], max_lag: usize) -> Vec<f64> {
+    let n = series.len();
+    let mean = series.iter().sum::<f64>() / n as f64;
+    let variance = series.iter().map(|x| (x - mean).powi(2)).sum::<f64>();
+    (0..=max_lag)
+        .map(|lag| {
+            if variance == 0.0 || lag >= n {
+                return 0.0;
+            }
+            series.iter()
+                .zip(series[lag..].iter())
+                .map(|(x, y)| (x - mean) * (y - mean))
+                .sum::<f64>() / variance
+        })
+        .collect()
+}
+
+/// Computes the autocorrelation of the norms of the embedded vectors
+///
+/// The norm of a vector is its distance from the origin in `metric`.
+pub fn norm_autocorrelation<C>(coord: &C, slice: &[f64], max_lag: usize, metric: Metric) -> Vec<f64>
+where
+    C: DelayCoordinates,
+{
+    let origin = vec![0.0; coord.dimension()];
+    let norms = coord.mapping_iter(slice)
+        .map(|view| metric.distance(&view.to_vec(), &origin))
+        .collect::<Vec<f64>>();
+    autocorrelation(&norms, max_lag)
+}
+
+/// Computes the transition-probability matrix between discretized states
+///
+/// Each coordinate is divided into `bins_per_dim` equal-width bins over its range on the
+/// trajectory, and each embedded point is assigned to a cell of the resulting grid.
+/// The second element of the result lists the visited cells as bin indices per coordinate in
+/// order of first visit, and the rows and columns of the matrix refer to these cells.
+/// The rows are normalized; the row of a cell without outgoing transitions is zero.
+pub fn transition_matrix<C>(coord: &C, slice: &[f64], bins_per_dim: usize) -> (Vec<Vec<f64>>, Vec<Vec<usize>>)
+where
+    C: DelayCoordinates,
+{
+    let points = crate::embed(coord, slice);
+    let mut cells: Vec<Vec<usize>> = Vec::new();
+    let states = cell_indices(&points, bins_per_dim)
+        .into_iter()
+        .map(|cell| {
+            match cells.iter().position(|c| *c == cell) {
+                Some(state) => state,
+                None => {
+                    cells.push(cell);
+                    cells.len() - 1
+                }
+            }
+        })
+        .collect::<Vec<usize>>();
+    let mut matrix = vec![vec![0.0; cells.len()]; cells.len()];
+    for w in states.windows(2) {
+        matrix[w[0]][w[1]] += 1.0;
+    }
+    for row in matrix.iter_mut() {
+        let total = row.iter().sum::<f64>();
+        if total > 0.0 {
+            row.iter_mut().for_each(|p| *p /= total);
+        }
+    }
+    (matrix, cells)
+}
+
+/// Computes the fraction of cells of the state space occupied by the trajectory
+///
+/// The bounding box of the trajectory is divided into `bins_per_dim` equal-width bins in each
+/// coordinate, so there are `bins_per_dim.pow(dimension)` cells in total.
+/// A low value suggests that the attractor is under-sampled.
+pub fn space_filling<C>(coord: &C, slice: &[f64], bins_per_dim: usize) -> f64
+where
+    C: DelayCoordinates,
+{
+    let points = crate::embed(coord, slice);
+    let mut cells = cell_indices(&points, bins_per_dim);
+    cells.sort();
+    cells.dedup();
+    cells.len() as f64 / (bins_per_dim as f64).powi(coord.dimension() as i32)
+}
+
+/// Bins the embedded series into a 2D histogram over two coordinates
+///
+/// The ranges of the coordinates `coord_x` and `coord_y` are divided into `nx` and `ny`
+/// equal-width bins. The element `[y][x]` of the returned image is the number of points in the
+/// `x`-th bin of `coord_x` and the `y`-th bin of `coord_y`, so the rows run along `coord_y`.
+///
+/// # Panics
+///
+/// Panics if a coordinate index is not less than the dimension or if `nx` or `ny` is zero.
+pub fn density_image<C>(coord: &C, slice: &[f64], coord_x: usize, coord_y: usize, nx: usize, ny: usize) -> Vec<Vec<u32>>
+where
+    C: DelayCoordinates,
+{
+    assert!(coord_x < coord.dimension() && coord_y < coord.dimension(), "coordinate index out of range");
+    assert!(nx > 0 && ny > 0, "number of bins must be positive");
+    let (xs, ys): (Vec<f64>, Vec<f64>) = coord.mapping_iter(slice)
+        .map(|view| (view[coord_x], view[coord_y]))
+        .unzip();
+    let mut image = vec![vec![0; nx]; ny];
+    let bins_x = crate::preprocess::discretize(&xs, nx);
+    let bins_y = crate::preprocess::discretize(&ys, ny);
+    for (&x, &y) in bins_x.iter().zip(bins_y.iter()) {
+        image[y][x] += 1;
+    }
+    image
+}
+
+/// Assigns each point to a cell of a grid with `bins` equal-width bins per coordinate
+fn cell_indices(points: &[Vec<f64>], bins: usize) -> Vec<Vec<usize>> {
+    let dim = points.first().map_or(0, |p| p.len());
+    let columns = (0..dim)
+        .map(|k| {
+            let column = points.iter().map(|p| p[k]).collect::<Vec<f64>>();
+            crate::preprocess::discretize(&column, bins)
+        })
+        .collect::<Vec<_>>();
+    (0..points.len())
+        .map(|i| columns.iter().map(|column| column[i]).collect())
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{DelayCoordinates, Error, ForwardDelayCoordinates, NonUniformDelayCoordinates};
+    use crate::analysis::{autocorrelation, distance, distances_from, neighbor_counts};
+    use crate::analysis::{norm_autocorrelation, smoothed_trajectory, space_filling, transition_matrix};
+    use crate::analysis::{BallIndex, NeighborSearch, TieBreak, nearest_neighbor};
+    use crate::analysis::{mean_pairwise_distance, mean_pairwise_distance_sampled, registration_lag};
+    use crate::analysis::{extrema_states, instantaneous_frequency, phase, rotation_number};
+    use crate::analysis::{DistanceCache, neighbor_counts_cached, trajectory_length};
+    use crate::analysis::{coordinate_variances, max_variance_coordinate, pointwise_prediction_error};
+    use crate::analysis::{anomaly_scores, cross_prediction_matrix, resample_trajectory};
+    use crate::analysis::{StreamingQuantile, density_image, effective_dimension, find_analog};
+    use crate::analysis::{injectivity_violations, ssa_cumulative_variance, ssa_reconstruct};
+    use crate::analysis::{all_nearest_neighbors, higuchi_fd, multiscale_entropy, sample_entropy};
+    use crate::analysis::{density_ranking, determinism_test, kantz, spectral_entropy, stationarity_test};
+    use crate::analysis::{correlation_dimension_ci, project, renyi_dimensions, state_decorrelation_time};
+    use crate::analysis::{active_information_storage, entropy_rate, hankel_dmd, transfer_entropy, HankelDmd};
+    use crate::testutil::{sine, white_noise};
+    use crate::metric::Metric;
+
+    #[test]
+    fn test_neighbor_counts() {
+        let data = (0..20).map(|n| n as f64).collect::<Vec<f64>>();
+        let coord = ForwardDelayCoordinates {
+            delay: 1,
+            dimension: 2,
+        };
+        let counts = neighbor_counts(&coord, &data, 2.5, Metric::Maximum, 0);
+        assert_eq!(counts.len(), 19);
+        assert_eq!(&counts[..3], &[2, 3, 4]);
+        assert!(counts[2..17].iter().all(|&c| c == 4));
+        assert_eq!(&counts[16..], &[4, 3, 2]);
+        let counts = neighbor_counts(&coord, &data, 2.5, Metric::Maximum, 1);
+        assert!(counts[2..17].iter().all(|&c| c == 2));
+    }
+
+    #[test]
+    fn test_distances_from() {
+        let data = sine(50, 7.3);
+        let coord = ForwardDelayCoordinates {
+            delay: 2,
+            dimension: 3,
+        };
+        let distances = distances_from(&coord, &data, 5, Metric::Euclidean);
+        assert_eq!(distances.len(), 46);
+        for (j, &d) in distances.iter().enumerate() {
+            assert_eq!(d, distance(&coord, &data, 5, j, Metric::Euclidean));
+        }
+        assert_eq!(distances[5], 0.0);
+    }
+
+    #[test]
+    fn test_smoothed_trajectory() {
+        let data = white_noise(200, 3);
+        let coord = ForwardDelayCoordinates {
+            delay: 1,
+            dimension: 2,
+        };
+        let step_variance = |points: &[Vec<f64>]| {
+            points.windows(2)
+                .map(|w| Metric::Euclidean.distance(&w[0], &w[1]).powi(2))
+                .sum::<f64>() / (points.len() - 1) as f64
+        };
+        let raw = smoothed_trajectory(&coord, &data, 1.0);
+        let smoothed = smoothed_trajectory(&coord, &data, 0.2);
+        assert_eq!(raw, crate::embed(&coord, &data));
+        assert_eq!(smoothed.len(), raw.len());
+        assert_eq!(smoothed[0], raw[0]);
+        assert!(step_variance(&smoothed) < 0.5 * step_variance(&raw));
+    }
+
+    #[test]
+    fn test_autocorrelation() {
+        let acf = autocorrelation(&[1.0, -1.0, 1.0, -1.0], 2);
+        assert_eq!(acf, vec![1.0, -0.75, 0.5]);
+        assert_eq!(autocorrelation(&[2.0, 2.0], 1), vec![0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_norm_autocorrelation_periodic() {
+        let data = sine(400, 20.0);
+        let coord = ForwardDelayCoordinates {
+            delay: 1,
+            dimension: 2,
+        };
+        let acf = norm_autocorrelation(&coord, &data, 30, Metric::Euclidean);
+        assert_eq!(acf.len(), 31);
+        assert!(acf[5] < 0.0);
+        assert!(acf[10] > 0.9);
+        assert!(acf[20] > 0.9);
+    }
+
+    #[test]
+    fn test_transition_matrix_cycle() {
+        let data = (0..30).map(|n| (n % 3) as f64).collect::<Vec<f64>>();
+        let coord = ForwardDelayCoordinates {
+            delay: 1,
+            dimension: 2,
+        };
+        let (matrix, cells) = transition_matrix(&coord, &data, 3);
+        assert_eq!(cells, vec![vec![1, 0], vec![2, 1], vec![0, 2]]);
+        assert_eq!(matrix, vec![
+            vec![0.0, 1.0, 0.0],
+            vec![0.0, 0.0, 1.0],
+            vec![1.0, 0.0, 0.0],
+        ]);
+    }
+
+    #[test]
+    fn test_space_filling() {
+        let data = white_noise(2000, 4);
+        let coord = ForwardDelayCoordinates {
+            delay: 1,
+            dimension: 2,
+        };
+        let long = space_filling(&coord, &data, 10);
+        let short = space_filling(&coord, &data[..31], 10);
+        assert!(long > 0.95);
+        assert!(short <= 0.3);
+    }
+
+    #[test]
+    fn test_neighbor_search_tie_break() {
+        let points = [0.0, 5.0, 1.0, 5.0, 5.0, 1.0, 9.0, -1.0].iter().map(|&x| vec![x]).collect::<Vec<_>>();
+        let mut search = NeighborSearch::new(Metric::Euclidean);
+        assert_eq!(search.nearest(&points, 0), Some(2));
+        search.tie_break = TieBreak::LastIndex;
+        assert_eq!(search.nearest(&points, 0), Some(7));
+        search.tie_break = TieBreak::FarthestInTime;
+        assert_eq!(search.k_nearest(&points, 3, 2), vec![1, 4]);
+        search.theiler = 2;
+        assert_eq!(search.k_nearest(&points, 3, 3), vec![6, 0, 7]);
+    }
+
+    #[test]
+    fn test_neighbor_search_skip_zero() {
+        // A flat segment at 2.0 and a nearby value
+        let points = [2.0, 2.0, 2.0, 2.0, 2.5, 0.0].iter().map(|&x| vec![x]).collect::<Vec<_>>();
+        let mut search = NeighborSearch::new(Metric::Euclidean);
+        assert_eq!(search.nearest(&points, 1), Some(0));
+        assert_eq!(search.k_nearest(&points, 1, 3), vec![0, 2, 3]);
+        search.skip_zero = true;
+        assert_eq!(search.nearest(&points, 1), Some(4));
+        assert_eq!(search.k_nearest(&points, 1, 3), vec![4, 5]);
+    }
+
+    #[test]
+    fn test_neighbor_search_nan() {
+        let points = [0.0, f64::NAN, 3.0, 0.5].iter().map(|&x| vec![x]).collect::<Vec<_>>();
+        let search = NeighborSearch::new(Metric::Euclidean);
+        assert_eq!(search.nearest(&points, 0), Some(3));
+        assert_eq!(search.k_nearest(&points, 2, 3), vec![3, 0]);
+        assert_eq!(search.nearest(&points, 1), None);
+    }
+
+    #[test]
+    fn test_nearest_neighbor() {
+        let data = vec![0.0, 3.0, 1.0, 2.9, 0.1];
+        let coord = ForwardDelayCoordinates {
+            delay: 1,
+            dimension: 1,
+        };
+        assert_eq!(nearest_neighbor(&coord, &data, 0, Metric::Euclidean, 0), Some(4));
+        assert_eq!(nearest_neighbor(&coord, &data, 1, Metric::Euclidean, 0), Some(3));
+        assert_eq!(nearest_neighbor(&coord, &data, 1, Metric::Euclidean, 2), Some(4));
+    }
+
+    #[test]
+    fn test_mean_pairwise_distance() {
+        let data = sine(40, 9.0);
+        let coord = ForwardDelayCoordinates {
+            delay: 2,
+            dimension: 2,
+        };
+        let points = crate::embed(&coord, &data);
+        let mut sum = 0.0;
+        let mut count = 0;
+        for i in 0..points.len() {
+            for j in 0..points.len() {
+                if i != j {
+                    sum += Metric::Euclidean.distance(&points[i], &points[j]);
+                    count += 1;
+                }
+            }
+        }
+        let mean = mean_pairwise_distance(&coord, &data, Metric::Euclidean);
+        assert!((mean - sum / count as f64).abs() < 1e-12);
+        let sampled = mean_pairwise_distance_sampled(&coord, &data, Metric::Euclidean, 20);
+        assert!((sampled - mean).abs() < 0.1 * mean);
+        assert_eq!(mean_pairwise_distance(&coord, &data[..3], Metric::Euclidean), 0.0);
+    }
+
+    #[test]
+    fn test_registration_lag() {
+        let x = white_noise(200, 5);
+        let y = [vec![0.0; 7], x.clone()].concat();
+        let coord = ForwardDelayCoordinates {
+            delay: 1,
+            dimension: 3,
+        };
+        assert_eq!(registration_lag(&coord, &x, &y, Metric::Euclidean, 20), 7);
+        assert_eq!(registration_lag(&coord, &y, &x, Metric::Euclidean, 20), -7);
+    }
+
+    #[test]
+    fn test_ball_index() {
+        let data = white_noise(300, 6);
+        let coord = ForwardDelayCoordinates {
+            delay: 1,
+            dimension: 3,
+        };
+        for &metric in [Metric::Euclidean, Metric::Maximum].iter() {
+            let index = BallIndex::new(&coord, &data, metric);
+            let points = index.points().to_vec();
+            for &r in [0.05, 0.3, 5.0].iter() {
+                for query in points.iter().step_by(17) {
+                    let brute = (0..points.len())
+                        .filter(|&j| metric.distance(&points[j], query) <= r)
+                        .collect::<Vec<usize>>();
+                    assert_eq!(index.within_radius(query, r), brute);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_extrema_states() {
+        let data = sine(60, 20.0);
+        let coord = ForwardDelayCoordinates {
+            delay: 3,
+            dimension: 2,
+        };
+        let extrema = extrema_states(&coord, &data, 0);
+        let indices = extrema.iter().map(|&(i, _)| i).collect::<Vec<usize>>();
+        assert_eq!(indices, vec![2, 12, 22, 32, 42, 52]);
+        for (k, (_, state)) in extrema.iter().enumerate() {
+            let peak = if k % 2 == 0 { 1.0 } else { -1.0 };
+            assert!((state[0] - peak).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_phase_circle() {
+        let data = sine(205, 20.0);
+        let coord = ForwardDelayCoordinates {
+            delay: 5,
+            dimension: 2,
+        };
+        let phases = phase(&coord, &data);
+        assert_eq!(phases.len(), 200);
+        for w in phases.windows(2) {
+            assert!((w[1] - w[0] - 2.0 * std::f64::consts::PI / 20.0).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_instantaneous_frequency() {
+        let data = sine(308, 12.0);
+        let coord = ForwardDelayCoordinates {
+            delay: 3,
+            dimension: 2,
+        };
+        let frequency = instantaneous_frequency(&coord, &data);
+        assert_eq!(frequency.len(), 304);
+        let omega = 2.0 * std::f64::consts::PI / 12.0;
+        assert!(frequency.iter().all(|f| (f - omega).abs() < 0.05 * omega));
+    }
+
+    #[test]
+    fn test_rotation_number() {
+        let rho = (5.0f64.sqrt() - 1.0) / 4.0;
+        let data = (0..5000)
+            .map(|n| (2.0 * std::f64::consts::PI * rho * n as f64).cos())
+            .collect::<Vec<f64>>();
+        let coord = ForwardDelayCoordinates {
+            delay: 1,
+            dimension: 2,
+        };
+        assert!((rotation_number(&coord, &data).abs() - rho).abs() < 1e-3);
+        assert_eq!(rotation_number(&coord, &data[..1]), 0.0);
+    }
+
+    #[test]
+    fn test_distance_cache() {
+        let data = white_noise(60, 17);
+        let coord = ForwardDelayCoordinates {
+            delay: 2,
+            dimension: 3,
+        };
+        let cache = DistanceCache::new(&coord, &data, Metric::Euclidean);
+        assert_eq!(cache.len(), 56);
+        for i in 0..cache.len() {
+            let direct = distances_from(&coord, &data, i, Metric::Euclidean);
+            for (j, d) in direct.iter().enumerate() {
+                assert_eq!(cache.get(i, j), *d);
+            }
+        }
+        assert_eq!(neighbor_counts_cached(&cache, 0.8, 2),
+                   neighbor_counts(&coord, &data, 0.8, Metric::Euclidean, 2));
+    }
+
+    #[test]
+    fn test_trajectory_length_ramp() {
+        let data = (0..20).map(|t| 0.5 * t as f64).collect::<Vec<f64>>();
+        let coord = ForwardDelayCoordinates {
+            delay: 2,
+            dimension: 4,
+        };
+        assert_eq!(trajectory_length(&coord, &data, Metric::Manhattan), 13.0 * 2.0);
+        assert_eq!(trajectory_length(&coord, &data, Metric::Maximum), 13.0 * 0.5);
+        assert_eq!(trajectory_length(&coord, &data[..7], Metric::Euclidean), 0.0);
+    }
+
+    #[test]
+    fn test_coordinate_variances() {
+        // The amplitude grows with time, so the newest coordinate varies the most.
+        let data = sine(200, 10.0).iter()
+            .enumerate()
+            .map(|(t, x)| x * t as f64)
+            .collect::<Vec<f64>>();
+        let coord = ForwardDelayCoordinates {
+            delay: 30,
+            dimension: 3,
+        };
+        let variances = coordinate_variances(&coord, &data);
+        assert_eq!(variances.len(), 3);
+        assert!(variances[0] > variances[1] && variances[1] > variances[2]);
+        assert_eq!(max_variance_coordinate(&coord, &data), Some(0));
+
+        let reversed = coord.time_reversed();
+        assert_eq!(max_variance_coordinate(&reversed, &data), Some(2));
+
+        // The last sample is only in the newest coordinate of the last window
+        let mut gapped = data.clone();
+        gapped[199] = f64::NAN;
+        assert!(coordinate_variances(&coord, &gapped)[0].is_nan());
+        assert_eq!(max_variance_coordinate(&coord, &gapped), Some(1));
+        assert_eq!(max_variance_coordinate(&coord, &[f64::NAN; 61]), None);
+    }
+
+    #[test]
+    fn test_pointwise_prediction_error() {
+        let mut data = sine(400, 20.0);
+        data[250] += 3.0;
+        let coord = ForwardDelayCoordinates {
+            delay: 5,
+            dimension: 2,
+        };
+        let errors = pointwise_prediction_error(&coord, &data, 3, 2);
+        assert_eq!(errors.len(), 393);
+        // The point whose predicted sample is the spike
+        let hit = 250 - 5 - 2;
+        let worst = (0..errors.len())
+            .max_by(|&a, &b| errors[a].total_cmp(&errors[b]))
+            .unwrap();
+        assert_eq!(worst, hit);
+        let mut sorted = errors.clone();
+        sorted.sort_by(|a, b| a.total_cmp(b));
+        assert!(errors[hit] > 10.0 * sorted[sorted.len() / 2]);
+    }
+
+    #[test]
+    fn test_cross_prediction_matrix() {
+        // x drives y, and z is independent noise
+        let (mut x, mut y) = (0.4, 0.2);
+        let mut xs = Vec::new();
+        let mut ys = Vec::new();
+        for _ in 0..500 {
+            let next_x = x * (3.8 - 3.8 * x);
+            let next_y = y * (3.5 - 3.5 * y - 0.1 * x);
+            x = next_x;
+            y = next_y;
+            xs.push(x);
+            ys.push(y);
+        }
+        let zs = white_noise(500, 3);
+        let skill = cross_prediction_matrix(&[&xs, &ys, &zs], 1, 2, 3);
+        assert_eq!(skill.len(), 3);
+        // Recovering the driver x from y works better than recovering y from x
+        assert!(skill[1][0] > 0.5);
+        assert!(skill[0][1] < skill[1][0]);
+        assert!(skill[2][0].abs() < 0.2);
+        assert!(skill[0][0] > 0.9);
+    }
+
+    #[test]
+    fn test_resample_trajectory() {
+        let data = (0..104).map(|t| t as f64).collect::<Vec<f64>>();
+        let coord = ForwardDelayCoordinates {
+            delay: 2,
+            dimension: 3,
+        };
+        let resampled = resample_trajectory(&coord, &data, 34);
+        assert_eq!(resampled.len(), 34);
+        assert_eq!(resampled[0], vec![4.0, 2.0, 0.0]);
+        assert_eq!(resampled[33], vec![103.0, 101.0, 99.0]);
+        assert_eq!(resampled[1], vec![7.0, 5.0, 3.0]);
+        assert_eq!(resample_trajectory(&coord, &data[..6], 4).len(), 4);
+        assert!(resample_trajectory(&coord, &data[..4], 4).is_empty());
+    }
+
+    #[test]
+    fn test_anomaly_scores() {
+        let mut data = sine(300, 20.0);
+        data[150] = 2.5;
+        let coord = ForwardDelayCoordinates {
+            delay: 5,
+            dimension: 2,
+        };
+        let scores = anomaly_scores(&coord, &data, 4, Metric::Euclidean, 2);
+        assert_eq!(scores.len(), 295);
+        let mut ranked = (0..scores.len()).collect::<Vec<usize>>();
+        ranked.sort_by(|&a, &b| scores[b].total_cmp(&scores[a]));
+        // The windows starting at 145 and 150 contain the anomaly
+        let mut top = ranked[..2].to_vec();
+        top.sort();
+        assert_eq!(top, vec![145, 150]);
+        assert!(scores[145] > 3.0);
+        assert!(anomaly_scores(&coord, &[1.0; 20], 2, Metric::Euclidean, 0).iter().all(|&s| s == 0.0));
+    }
+
+    #[test]
+    fn test_streaming_quantile() {
+        let data = white_noise(10000, 21);
+        let mut sorted = data.clone();
+        sorted.sort_by(|a, b| a.total_cmp(b));
+        for &p in [0.1, 0.5, 0.9].iter() {
+            let mut estimator = StreamingQuantile::new(p);
+            assert_eq!(estimator.quantile(), None);
+            for &x in data.iter() {
+                estimator.push(x);
+            }
+            let exact = sorted[(p * (sorted.len() - 1) as f64) as usize];
+            assert_eq!(estimator.count(), 10000);
+            assert!((estimator.quantile().unwrap() - exact).abs() < 0.02);
+        }
+        let mut estimator = StreamingQuantile::new(0.5);
+        for &x in [3.0, 1.0, 2.0].iter() {
+            estimator.push(x);
+        }
+        assert_eq!(estimator.quantile(), Some(2.0));
+    }
+
+    #[test]
+    fn test_effective_dimension() {
+        let coord = ForwardDelayCoordinates {
+            delay: 1,
+            dimension: 4,
+        };
+        let slow = effective_dimension(&coord, &sine(2000, 500.0)).unwrap();
+        assert!((slow - 1.0).abs() < 0.01);
+        let noise = effective_dimension(&coord, &white_noise(5000, 13)).unwrap();
+        assert!((noise - 4.0).abs() < 0.2);
+        assert_eq!(effective_dimension(&coord, &[1.0; 10]), Err(Error::DegenerateSeries));
+    }
+
+    #[test]
+    fn test_find_analog() {
+        let series = white_noise(300, 21);
+        let coord = ForwardDelayCoordinates {
+            delay: 2,
+            dimension: 3,
+        };
+        let query = coord.mapping_iter(&series).nth(123).unwrap().to_vec();
+        assert_eq!(find_analog(&coord, &series, &query, Metric::Euclidean), Some(123));
+        let shifted = query.iter().map(|x| x + 1e-3).collect::<Vec<f64>>();
+        assert_eq!(find_analog(&coord, &series, &shifted, Metric::Maximum), Some(123));
+        assert_eq!(find_analog(&coord, &series[..4], &query, Metric::Euclidean), None);
+        let mut gapped = series.clone();
+        gapped[0] = f64::NAN;
+        assert_eq!(find_analog(&coord, &gapped, &query, Metric::Euclidean), Some(123));
+        assert_eq!(find_analog(&coord, &[f64::NAN; 5], &query, Metric::Euclidean), None);
+    }
+
+    #[test]
+    fn test_density_image() {
+        let coord = ForwardDelayCoordinates {
+            delay: 10,
+            dimension: 2,
+        };
+        let image = density_image(&coord, &sine(2010, 40.0), 0, 1, 10, 10);
+        assert_eq!(image.len(), 10);
+        assert_eq!(image.iter().flatten().sum::<u32>(), 2000);
+        assert!(image[3..7].iter().all(|row| row[3..7].iter().all(|&count| count == 0)));
+        assert!(image[0][0] == 0 && image[9][9] == 0);
+        assert!(image[0][4] > 0 && image[4][0] > 0 && image[9][5] > 0 && image[5][9] > 0);
+    }
+
+    #[test]
+    fn test_injectivity_violations() {
+        // Three quarters of a period, so the rising and falling parts take the same values
+        let series = sine(150, 200.0);
+        let line = ForwardDelayCoordinates {
+            delay: 50,
+            dimension: 1,
+        };
+        let folded = injectivity_violations(&line, &series, 1e-9, 5);
+        assert!(folded.len() > 30);
+        assert!(folded.contains(&(20, 80)));
+        let plane = ForwardDelayCoordinates {
+            delay: 50,
+            dimension: 2,
+        };
+        assert!(injectivity_violations(&plane, &series, 1e-9, 5).is_empty());
+    }
+
+    #[test]
+    fn test_ssa_reconstruct() {
+        let coord = ForwardDelayCoordinates {
+            delay: 1,
+            dimension: 6,
+        };
+        let noise = white_noise(200, 23);
+        let full = ssa_reconstruct(&coord, &noise, &[0, 1, 2, 3, 4, 5]);
+        assert_eq!(full.len(), 200);
+        assert!(full.iter().zip(noise.iter()).all(|(x, y)| (x - y).abs() < 1e-9));
+
+        let clean = sine(400, 10.0 * std::f64::consts::PI);
+        let noisy = clean.iter().zip(white_noise(400, 24).iter()).map(|(x, e)| x + 0.3 * e).collect::<Vec<f64>>();
+        let coord = ForwardDelayCoordinates {
+            delay: 1,
+            dimension: 20,
+        };
+        let denoised = ssa_reconstruct(&coord, &noisy, &[0, 1]);
+        let error = |x: &[f64]| x.iter().zip(clean.iter()).map(|(a, b)| (a - b).powi(2)).sum::<f64>();
+        assert!(error(&denoised) < 0.3 * error(&noisy));
+        let cumulative = ssa_cumulative_variance(&coord, &noisy);
+        assert!(cumulative[1] > 0.8 && (cumulative[19] - 1.0).abs() < 1e-12);
+        assert!(cumulative.windows(2).all(|w| w[0] <= w[1]));
+    }
+
+    #[test]
+    fn test_entropy_rate() {
+        let periodic = entropy_rate(&sine(5000, 7.3), 4, 4);
+        let noise = entropy_rate(&white_noise(5000, 25), 4, 4);
+        assert!(periodic < 0.3);
+        assert!((noise - 4.0f64.ln()).abs() < 0.05);
+    }
+
+    #[test]
+    fn test_active_information_storage() {
+        let mut logistic = vec![0.3];
+        for t in 0..5000 {
+            let x = logistic[t];
+            logistic.push(4.0 * x * (1.0 - x));
+        }
+        let deterministic = active_information_storage(&logistic, 1, 1, 8);
+        let noise = active_information_storage(&white_noise(5000, 26), 1, 1, 8);
+        assert!(deterministic > 1.0);
+        assert!(noise < 0.05);
+        assert_eq!(active_information_storage(&[1.0, 2.0], 1, 3, 8), 0.0);
+    }
+
+    #[test]
+    fn test_transfer_entropy() {
+        // The driver x forces y with a lag of one step
+        let x = white_noise(5000, 27);
+        let e = white_noise(5000, 28);
+        let mut y = vec![0.0];
+        for t in 0..4999 {
+            y.push(0.8 * x[t] + 0.2 * e[t]);
+        }
+        let forward = transfer_entropy(&x, &y, 1, 1, 4);
+        let backward = transfer_entropy(&y, &x, 1, 1, 4);
+        assert!(forward > 0.3);
+        assert!(backward < 0.05);
+        assert_eq!(transfer_entropy(&x[..1], &y, 1, 1, 4), 0.0);
+    }
+
+    #[test]
+    fn test_hankel_dmd() {
+        let (omega, gamma) = (0.3, 0.02);
+        let series = (0..200)
+            .map(|t| (-gamma * t as f64).exp() * (omega * t as f64 + 0.4).cos())
+            .collect::<Vec<f64>>();
+        let coord = ForwardDelayCoordinates {
+            delay: 1,
+            dimension: 10,
+        };
+        let HankelDmd { eigenvalues, modes } = hankel_dmd(&coord, &series, 2);
+        assert_eq!(eigenvalues.len(), 2);
+        // The conjugate pair has the same modulus up to rounding, so either may come first
+        let (re, im) = eigenvalues.iter().cloned().find(|&(_, im)| im > 0.0).unwrap();
+        assert!((im.atan2(re) - omega).abs() < 1e-6);
+        assert!((-re.hypot(im).ln() - gamma).abs() < 1e-6);
+        assert!(eigenvalues.iter().any(|&(r, i)| (r - re).abs() < 1e-6 && (i + im).abs() < 1e-6));
+        // The coordinate i is the sample i steps before the newest one, so a mode is a
+        // geometric sequence with the ratio 1 / lambda
+        assert_eq!(modes.len(), 2);
+        for (&lambda, mode) in eigenvalues.iter().zip(modes.iter()) {
+            assert_eq!(mode.len(), 10);
+            for pair in mode.windows(2) {
+                let next = (pair[1].0 * lambda.0 - pair[1].1 * lambda.1, pair[1].0 * lambda.1 + pair[1].1 * lambda.0);
+                assert!((next.0 - pair[0].0).abs() < 1e-6 && (next.1 - pair[0].1).abs() < 1e-6);
+            }
+        }
+        assert_eq!(hankel_dmd(&coord, &series, 5).eigenvalues.len(), 2);
+        let non_uniform = NonUniformDelayCoordinates {
+            lags: vec![0, 2, 5],
+        };
+        let eigenvalues = hankel_dmd(&non_uniform, &series, 2).eigenvalues;
+        assert!((eigenvalues[0].1.abs().atan2(eigenvalues[0].0) - omega).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_all_nearest_neighbors() {
+        let series = white_noise(120, 29);
+        let coord = ForwardDelayCoordinates {
+            delay: 2,
+            dimension: 3,
+        };
+        let all = all_nearest_neighbors(&coord, &series, Metric::Maximum, 3);
+        assert_eq!(all.len(), 116);
+        for (i, &nn) in all.iter().enumerate() {
+            assert_eq!(nn, nearest_neighbor(&coord, &series, i, Metric::Maximum, 3));
+            assert!(nn.is_some());
+        }
+        assert_eq!(all_nearest_neighbors(&coord, &series[..7], Metric::Maximum, 3), vec![None, None, None]);
+    }
+
+    #[test]
+    fn test_higuchi_fd() {
+        let noise = higuchi_fd(&white_noise(5000, 30), 10).unwrap();
+        assert!((noise - 2.0).abs() < 0.05);
+        let smooth = higuchi_fd(&sine(5000, 500.0), 10).unwrap();
+        assert!((smooth - 1.0).abs() < 0.05);
+        assert!(higuchi_fd(&[1.0, 2.0], 10).unwrap().is_nan());
+        assert_eq!(higuchi_fd(&[3.0; 100], 10), Err(Error::DegenerateSeries));
+    }
+
+    #[test]
+    fn test_multiscale_entropy() {
+        let white = white_noise(4000, 31);
+        // Pink noise by the Voss method: octaves of noise held for doubling durations
+        let octaves = (0..10).map(|k| white_noise((4000 >> k) + 1, 40 + k as u64)).collect::<Vec<_>>();
+        let pink = (0..4000)
+            .map(|t| octaves.iter().enumerate().map(|(k, o)| o[t >> k]).sum::<f64>())
+            .collect::<Vec<f64>>();
+        let sd = |x: &[f64]| {
+            let mean = x.iter().sum::<f64>() / x.len() as f64;
+            (x.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / x.len() as f64).sqrt()
+        };
+        let scales = [1, 2, 4, 8];
+        let white_mse = multiscale_entropy(&white, &scales, 2, 0.15 * sd(&white)).unwrap();
+        let pink_mse = multiscale_entropy(&pink, &scales, 2, 0.15 * sd(&pink)).unwrap();
+        assert!(white_mse[3] < white_mse[0] - 1.0);
+        assert!((pink_mse[3] - pink_mse[0]).abs() < 0.5);
+        assert!(pink_mse[3] > white_mse[3]);
+        assert!(sample_entropy(&[1.0, 2.0], 2, 0.1).unwrap().is_nan());
+        assert_eq!(sample_entropy(&[1.0; 20], 2, 0.1), Err(Error::DegenerateSeries));
+        assert_eq!(multiscale_entropy(&[1.0; 20], &scales, 2, 0.1), Err(Error::DegenerateSeries));
+    }
+
+    #[test]
+    fn test_density_ranking() {
+        // A dense cluster around 0 with isolated samples at 5, 20 and 10
+        let mut series = white_noise(40, 32).iter().map(|x| 0.01 * x).collect::<Vec<f64>>();
+        series[7] = 5.0;
+        series[19] = 20.0;
+        series[33] = 10.0;
+        let coord = ForwardDelayCoordinates {
+            delay: 1,
+            dimension: 1,
+        };
+        let ranking = density_ranking(&coord, &series, 3, Metric::Euclidean);
+        assert_eq!(ranking.len(), 40);
+        assert_eq!(ranking[37..], [7, 33, 19]);
+        assert!(density_ranking(&coord, &[], 3, Metric::Euclidean).is_empty());
+        series[25] = f64::NAN;
+        let ranking = density_ranking(&coord, &series, 3, Metric::Euclidean);
+        assert_eq!(ranking[36..], [7, 33, 19, 25]);
+    }
+
+    #[test]
+    fn test_spectral_entropy() {
+        let coord = ForwardDelayCoordinates {
+            delay: 7,
+            dimension: 10,
+        };
+        let tone = spectral_entropy(&coord, &sine(100, 8.0));
+        assert_eq!(tone.len(), 37);
+        assert!(tone.iter().all(|&h| h < 0.05));
+        let noise = spectral_entropy(&coord, &white_noise(100, 36));
+        assert!(noise.iter().all(|&h| h > 0.7));
+        assert_eq!(spectral_entropy(&coord, &[1.0; 64]), vec![0.0]);
+    }
+
+    #[test]
+    fn test_determinism_test() {
+        let flow = determinism_test(&sine(1000, 10.0 * std::f64::consts::PI), 8, 3, 5).unwrap();
+        let noise = determinism_test(&white_noise(1000, 37), 1, 3, 5).unwrap();
+        assert!(flow > 0.95);
+        assert!(noise.abs() < 0.15);
+        assert!(determinism_test(&[1.0, 2.0], 1, 3, 5).unwrap().is_nan());
+        assert_eq!(determinism_test(&[1.0; 100], 1, 3, 5), Err(Error::DegenerateSeries));
+    }
+
+    #[test]
+    fn test_stationarity_test() {
+        let coord = ForwardDelayCoordinates {
+            delay: 1,
+            dimension: 2,
+        };
+        let noise = white_noise(1200, 38);
+        let switching = noise.iter()
+            .enumerate()
+            .map(|(t, x)| if t < 600 { *x } else { 2.0 + 0.5 * x })
+            .collect::<Vec<f64>>();
+        assert!(stationarity_test(&noise, &coord, 4) < 0.02);
+        assert!(stationarity_test(&switching, &coord, 4) > 0.3);
+        assert!(stationarity_test(&noise[..5], &coord, 4).is_nan());
+    }
+
+    #[test]
+    fn test_kantz_logistic() {
+        let mut series = vec![0.3];
+        for t in 0..2000 {
+            let x = series[t];
+            series.push(4.0 * x * (1.0 - x));
+        }
+        let coord = ForwardDelayCoordinates {
+            delay: 1,
+            dimension: 2,
+        };
+        let curves = kantz(&coord, &series, &[0.005, 0.01, 1e-12], 2, 4);
+        assert_eq!(curves.len(), 3);
+        for curve in curves[..2].iter() {
+            assert_eq!(curve.len(), 5);
+            let slope = (curve[3] - curve[0]) / 3.0;
+            assert!((slope - 2.0f64.ln()).abs() < 0.2, "slope = {}", slope);
+        }
+        assert!(curves[2].is_empty());
+
+        // Quantized samples repeat exactly, so some neighborhoods are at distance 0 at step 0
+        let quantized = series.iter().map(|x| (x * 100.0).round() / 100.0).collect::<Vec<f64>>();
+        let curves = kantz(&coord, &quantized, &[0.01, 0.004], 2, 4);
+        assert!(curves[0].iter().all(|s| s.is_finite()));
+        assert!(curves[1][0].is_nan());
+        assert!(curves[1][1..].iter().all(|s| s.is_finite()));
+    }
+
+    #[test]
+    fn test_project() {
+        let coord = ForwardDelayCoordinates {
+            delay: 5,
+            dimension: 2,
+        };
+        let circle = sine(405, 20.0);
+        let projected = project(&coord, &circle, 2);
+        assert_eq!(projected.len(), 400);
+        assert!(projected.iter().all(|p| p.len() == 2 && (p[0].hypot(p[1]) - 1.0).abs() < 1e-9));
+        assert!(project(&coord, &circle, 1).iter().all(|p| p.len() == 1));
+        assert_eq!(project(&coord, &circle, 5)[0].len(), 2);
+    }
+
+    #[test]
+    fn test_state_decorrelation_time() {
+        let coord = ForwardDelayCoordinates {
+            delay: 10,
+            dimension: 2,
+        };
+        // D(tau) = 2 sin(pi tau / P) on a unit circle, whose size is 4 / pi
+        let short = state_decorrelation_time(&coord, &sine(1000, 40.0), Metric::Euclidean).unwrap();
+        assert_eq!(short, 6);
+        let coord = ForwardDelayCoordinates {
+            delay: 20,
+            dimension: 2,
+        };
+        let long = state_decorrelation_time(&coord, &sine(1000, 80.0), Metric::Euclidean).unwrap();
+        assert!((long as isize - 2 * short as isize).abs() <= 1);
+        assert_eq!(state_decorrelation_time(&coord, &[1.0; 50], Metric::Euclidean), Err(Error::DegenerateSeries));
+    }
+
+    #[test]
+    fn test_correlation_dimension_ci() {
+        let coord = ForwardDelayCoordinates {
+            delay: 1,
+            dimension: 2,
+        };
+        let radii = [0.2, 0.3, 0.4, 0.5];
+        let (small, lower, upper) = correlation_dimension_ci(&coord, &white_noise(200, 14), &radii, 40).unwrap();
+        assert!(lower <= small && small <= upper);
+        let (large, large_lower, large_upper) = correlation_dimension_ci(&coord, &white_noise(800, 14), &radii, 40).unwrap();
+        assert!(large_lower <= large && large <= large_upper);
+        assert!(large_upper - large_lower < upper - lower);
+        assert!((large - 2.0).abs() < 0.3);
+        let (nan, _, _) = correlation_dimension_ci(&coord, &white_noise(50, 14), &[0.0, 0.1], 10).unwrap();
+        assert_eq!(correlation_dimension_ci(&coord, &[1.0; 50], &radii, 10), Err(Error::DegenerateSeries));
+        assert!(nan.is_nan());
+    }
+
+    #[test]
+    fn test_renyi_dimensions() {
+        let coord = ForwardDelayCoordinates {
+            delay: 1,
+            dimension: 2,
+        };
+        let q_values = [0.0, 1.0, 2.0, 3.0];
+        let dimensions = renyi_dimensions(&coord, &white_noise(1500, 31), &q_values, &[0.1, 0.15, 0.2, 0.3]).unwrap();
+        assert_eq!(dimensions.len(), 4);
+        assert!(dimensions.iter().all(|d| (d - 2.0).abs() < 0.25));
+        assert!(dimensions.windows(2).all(|d| (d[0] - d[1]).abs() < 0.1));
+        assert!(renyi_dimensions(&coord, &white_noise(20, 31), &[2.0], &[0.0, 0.1]).unwrap()[0].is_nan());
+        assert_eq!(renyi_dimensions(&coord, &[1.0; 20], &[2.0], &[0.1]), Err(Error::DegenerateSeries));
+    }
+}
@@ -0,0 +1,64 @@
+//! Radix-2 fast Fourier transform
+//!
+//! Complex numbers are pairs of real and imaginary parts.
+
+use std::f64::consts::PI;
+
+/// Computes the discrete Fourier transform of `data` in place
+///
+/// The $k$-th element becomes $\sum_t x_t e^{-2 \pi i k t / n}$. The transform takes
+/// $O(n \log n)$ time by the iterative Cooley-Tukey algorithm.
+///
+/// # Panics
+///
+/// Panics if the length of `data` is not a power of two.
+pub(crate) fn fft(data: &mut [(f64, f64)]) {
+    let n = data.len();
+    assert!(n.is_power_of_two(), "length must be a power of two");
+    let bits = n.trailing_zeros();
+    for i in 0..n {
+        let j = i.reverse_bits().checked_shr(usize::BITS - bits).unwrap_or(0);
+        if i < j {
+            data.swap(i, j);
+        }
+    }
+    let mut len = 2;
+    while len <= n {
+        let angle = -2.0 * PI / len as f64;
+        for block in data.chunks_exact_mut(len) {
+            let (lower, upper) = block.split_at_mut(len / 2);
+            for (k, (a, b)) in lower.iter_mut().zip(upper.iter_mut()).enumerate() {
+                let (sin, cos) = (angle * k as f64).sin_cos();
+                let t = (b.0 * cos - b.1 * sin, b.0 * sin + b.1 * cos);
+                *b = (a.0 - t.0, a.1 - t.1);
+                *a = (a.0 + t.0, a.1 + t.1);
+            }
+        }
+        len *= 2;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::f64::consts::PI;
+    use crate::analysis::fft::fft;
+
+    #[test]
+    fn test_fft() {
+        let samples = [0.5, -1.0, 2.0, 0.25, 3.0, -0.75, 1.5, 0.0];
+        let mut data = samples.iter().map(|&x| (x, 0.0)).collect::<Vec<_>>();
+        fft(&mut data);
+        for (k, &(re, im)) in data.iter().enumerate() {
+            let (dre, dim) = samples.iter()
+                .enumerate()
+                .fold((0.0, 0.0), |(re, im), (t, x)| {
+                    let angle = 2.0 * PI * (k * t) as f64 / samples.len() as f64;
+                    (re + x * angle.cos(), im - x * angle.sin())
+                });
+            assert!((re - dre).abs() < 1e-12 && (im - dim).abs() < 1e-12);
+        }
+        let mut single = [(2.0, 1.0)];
+        fft(&mut single);
+        assert_eq!(single, [(2.0, 1.0)]);
+    }
+}
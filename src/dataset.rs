@@ -0,0 +1,95 @@
+//! Embedded series as datasets for learning algorithms
+
+use crate::DelayCoordinates;
+
+/// Indexed collection of feature vectors
+pub trait Dataset {
+    /// Number of samples
+    fn len(&self) -> usize;
+    /// Feature vector of the `i`-th sample
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i` is out of range.
+    fn get(&self, i: usize) -> Vec<f64>;
+
+    /// Returns `true` if there are no samples
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Embedded vectors of a series, optionally with prediction targets
+#[derive(Debug, Clone)]
+pub struct EmbeddedDataset {
+    points: Vec<Vec<f64>>,
+    targets: Option<Vec<f64>>,
+}
+
+impl EmbeddedDataset {
+    /// Embeds the series without targets
+    pub fn new<C>(coord: &C, slice: &[f64]) -> Self
+    where
+        C: DelayCoordinates,
+    {
+        EmbeddedDataset {
+            points: crate::embed(coord, slice),
+            targets: None,
+        }
+    }
+
+    /// Embeds the series with the sample `horizon` steps after each window as its target
+    ///
+    /// The windows whose target is beyond the end of the series are dropped.
+    pub fn with_targets<C>(coord: &C, slice: &[f64], horizon: usize) -> Self
+    where
+        C: DelayCoordinates,
+    {
+        let (points, targets) = crate::embed_with_targets(coord, slice, horizon);
+        EmbeddedDataset {
+            points,
+            targets: Some(targets),
+        }
+    }
+
+    /// Target of the `i`-th sample, if the dataset has targets
+    pub fn target(&self, i: usize) -> Option<f64> {
+        self.targets.as_ref().map(|targets| targets[i])
+    }
+}
+
+impl Dataset for EmbeddedDataset {
+    #[inline]
+    fn len(&self) -> usize { self.points.len() }
+
+    #[inline]
+    fn get(&self, i: usize) -> Vec<f64> {
+        self.points[i].clone()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::ForwardDelayCoordinates;
+    use crate::dataset::{Dataset, EmbeddedDataset};
+
+    #[test]
+    fn test_embedded_dataset() {
+        let data = (0..10).map(|t| t as f64).collect::<Vec<f64>>();
+        let coord = ForwardDelayCoordinates {
+            delay: 2,
+            dimension: 2,
+        };
+        let dataset = EmbeddedDataset::new(&coord, &data);
+        assert_eq!(dataset.len(), 8);
+        assert_eq!(dataset.get(3), vec![5.0, 3.0]);
+        assert_eq!(dataset.target(3), None);
+
+        let dataset = EmbeddedDataset::with_targets(&coord, &data, 1);
+        assert_eq!(dataset.len(), 7);
+        let samples = (0..dataset.len()).map(|i| (dataset.get(i), dataset.target(i).unwrap())).collect::<Vec<_>>();
+        assert_eq!(samples[0], (vec![2.0, 0.0], 3.0));
+        assert_eq!(samples[6], (vec![8.0, 6.0], 9.0));
+        assert!(EmbeddedDataset::with_targets(&coord, &data[..3], 1).is_empty());
+    }
+}
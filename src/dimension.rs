@@ -0,0 +1,171 @@
+//! Fractal dimensions of embedded attractors
+//!
+//! Pairs of embedded points closer in time than the Theiler window are excluded, as in `analysis`.
+
+use crate::DelayCoordinates;
+use crate::metric::Metric;
+
+/// Number of radii where the correlation sum is evaluated
+const RADII: usize = 24;
+/// Number of consecutive radii forming a scaling range
+const RANGE_LEN: usize = 6;
+
+/// Computes the correlation sum of the embedded series
+///
+/// The correlation sum $C(r)$ is the fraction of pairs $(i, j)$ with $|i - j|$ greater than
+/// `theiler` whose distance is at most `radius`.
+/// It is zero if there is no such pair.
+pub fn correlation_sum<C>(coord: &C, slice: &[f64], radius: f64, metric: Metric, theiler: usize) -> f64
+where
+    C: DelayCoordinates,
+{
+    let distances = pair_distances(&crate::embed(coord, slice), metric, theiler);
+    fraction_within(&distances, radius)
+}
+
+/// Computes the correlation sums of a set of points at each of `radii`
+///
+/// Unlike `correlation_dimension_of_points`, the distances are counted without being stored.
+pub(crate) fn correlation_sums(points: &[Vec<f64>], radii: &[f64], metric: Metric, theiler: usize) -> Vec<f64> {
+    let mut counts = vec![0usize; radii.len()];
+    let mut pairs = 0usize;
+    for (i, p) in points.iter().enumerate() {
+        for q in points.iter().skip(i+theiler+1) {
+            let d = metric.distance(p, q);
+            pairs += 1;
+            for (count, &r) in counts.iter_mut().zip(radii.iter()) {
+                if d <= r {
+                    *count += 1;
+                }
+            }
+        }
+    }
+    counts.iter()
+        .map(|&count| if pairs == 0 { 0.0 } else { count as f64 / pairs as f64 })
+        .collect()
+}
+
+/// Sorted distances of the pairs of points outside the Theiler window
+fn pair_distances(points: &[Vec<f64>], metric: Metric, theiler: usize) -> Vec<f64> {
+    let mut distances = Vec::new();
+    for (i, p) in points.iter().enumerate() {
+        distances.extend(points.iter().skip(i+theiler+1).map(|q| metric.distance(p, q)));
+    }
+    distances.sort_by(|a, b| a.total_cmp(b));
+    distances
+}
+
+/// Fraction of the sorted distances that are at most `radius`
+fn fraction_within(sorted: &[f64], radius: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    sorted.partition_point(|&d| d <= radius) as f64 / sorted.len() as f64
+}
+
+/// Slope of the least-squares line through the points $(x_i, y_i)$
+pub(crate) fn fit_slope(x: &[f64], y: &[f64]) -> f64 {
+    let n = x.len() as f64;
+    let mx = x.iter().sum::<f64>() / n;
+    let my = y.iter().sum::<f64>() / n;
+    let sxy = x.iter().zip(y.iter()).map(|(a, b)| (a - mx) * (b - my)).sum::<f64>();
+    let sxx = x.iter().map(|a| (a - mx).powi(2)).sum::<f64>();
+    sxy / sxx
+}
+
+/// Correlation dimension and the range of radii it is estimated on
+#[derive(Debug, Clone, PartialEq)]
+pub struct CorrelationDimension {
+    /// Slope of $\ln C(r)$ against $\ln r$ in the scaling range
+    pub dimension: f64,
+    /// Smallest and largest radii of the scaling range
+    pub scaling_range: (f64, f64),
+}
+
+/// Estimates the correlation dimension of the embedded series by the Grassberger–Procaccia method
+///
+/// See `correlation_dimension_of_points`.
+pub fn correlation_dimension<C>(coord: &C, slice: &[f64], metric: Metric, theiler: usize) -> Option<CorrelationDimension>
+where
+    C: DelayCoordinates,
+{
+    correlation_dimension_of_points(&crate::embed(coord, slice), metric, theiler)
+}
+
+/// Estimates the correlation dimension of a set of points by the Grassberger–Procaccia method
+///
+/// The correlation sum is evaluated at log-spaced radii from the 1% quantile of the pair
+/// distances up to their median. The scaling range is the run of consecutive radii whose local
+/// slopes of $\ln C(r)$ against $\ln r$ are positive and vary the least, and the dimension is
+/// the least-squares slope over it.
+/// Returns `None` if there are too few distinct positive distances.
+pub fn correlation_dimension_of_points(points: &[Vec<f64>], metric: Metric, theiler: usize) -> Option<CorrelationDimension> {
+    let distances = pair_distances(points, metric, theiler);
+    let positive = distances.partition_point(|&d| d <= 0.0);
+    let lower = *distances.get(positive.max(distances.len() / 100))?;
+    let upper = distances[distances.len() / 2];
+    if lower >= upper {
+        return None;
+    }
+    let log_r = (0..RADII)
+        .map(|k| lower.ln() + (upper.ln() - lower.ln()) * k as f64 / (RADII - 1) as f64)
+        .collect::<Vec<f64>>();
+    let log_c = log_r.iter()
+        .map(|r| fraction_within(&distances, r.exp()).ln())
+        .collect::<Vec<f64>>();
+    let slopes = log_r.windows(2)
+        .zip(log_c.windows(2))
+        .map(|(r, c)| (c[1] - c[0]) / (r[1] - r[0]))
+        .collect::<Vec<f64>>();
+    let spread = |start: usize| {
+        let window = &slopes[start..start+RANGE_LEN-1];
+        let mean = window.iter().sum::<f64>() / window.len() as f64;
+        window.iter().map(|s| (s - mean).powi(2)).sum::<f64>()
+    };
+    let start = (0..=RADII-RANGE_LEN)
+        .filter(|&start| slopes[start..start+RANGE_LEN-1].iter().all(|&s| s > 0.0))
+        .min_by(|&a, &b| spread(a).total_cmp(&spread(b)))?;
+    let range = start..start+RANGE_LEN;
+    Some(CorrelationDimension {
+        dimension: fit_slope(&log_r[range.clone()], &log_c[range.clone()]),
+        scaling_range: (log_r[range.start].exp(), log_r[range.end-1].exp()),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use crate::ForwardDelayCoordinates;
+    use crate::dimension::{correlation_dimension, correlation_sum};
+    use crate::metric::Metric;
+    use crate::testutil::{sine, white_noise};
+
+    #[test]
+    fn test_correlation_sum() {
+        let data = vec![0.0, 1.0, 0.0, 3.0];
+        let coord = ForwardDelayCoordinates {
+            delay: 1,
+            dimension: 1,
+        };
+        assert_eq!(correlation_sum(&coord, &data, 0.5, Metric::Euclidean, 0), 1.0 / 6.0);
+        assert_eq!(correlation_sum(&coord, &data, 0.5, Metric::Euclidean, 1), 1.0 / 3.0);
+        assert_eq!(correlation_sum(&coord, &data, 3.0, Metric::Euclidean, 0), 1.0);
+    }
+
+    #[test]
+    fn test_correlation_dimension() {
+        let coord = ForwardDelayCoordinates {
+            delay: 8,
+            dimension: 3,
+        };
+        let cycle = correlation_dimension(&coord, &sine(1000, 10.0 * std::f64::consts::PI), Metric::Euclidean, 10).unwrap();
+        assert!((cycle.dimension - 1.0).abs() < 0.15);
+        assert!(cycle.scaling_range.0 < cycle.scaling_range.1);
+
+        let coord = ForwardDelayCoordinates {
+            delay: 1,
+            dimension: 2,
+        };
+        let noise = correlation_dimension(&coord, &white_noise(1000, 5), Metric::Maximum, 0).unwrap();
+        assert!((noise.dimension - 2.0).abs() < 0.3);
+    }
+}
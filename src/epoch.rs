@@ -0,0 +1,41 @@
+//! Segmentation of long series into epochs
+//!
+//! Each epoch is a subslice of the series, so it can be embedded and analyzed on its own.
+
+/// Splits the series into epochs of `epoch_len` samples overlapping by `overlap` samples
+///
+/// The epochs start every `epoch_len - overlap` samples from the beginning and are returned
+/// with their start indices. The trailing samples that do not fill an epoch are dropped.
+///
+/// # Panics
+///
+/// Panics if `epoch_len` is zero or `overlap` is not less than `epoch_len`.
+pub fn split<T>(series: &[T], epoch_len: usize, overlap: usize) -> Vec<(usize, &[T])> {
+    assert!(epoch_len > 0, "epoch length must be positive");
+    assert!(overlap < epoch_len, "overlap must be less than the epoch length");
+    if series.len() < epoch_len {
+        return Vec::new();
+    }
+    (0..=series.len()-epoch_len)
+        .step_by(epoch_len - overlap)
+        .map(|start| (start, &series[start..start+epoch_len]))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::epoch::split;
+
+    #[test]
+    fn test_split() {
+        let data = (0..10).collect::<Vec<i32>>();
+        let epochs = split(&data, 4, 1);
+        assert_eq!(epochs.len(), 3);
+        assert_eq!(epochs[0], (0, &data[0..4]));
+        assert_eq!(epochs[1], (3, &data[3..7]));
+        assert_eq!(epochs[2], (6, &data[6..10]));
+        assert_eq!(epochs[0].1[3], epochs[1].1[0]);
+        assert_eq!(split(&data, 5, 0).len(), 2);
+        assert!(split(&data, 11, 2).is_empty());
+    }
+}
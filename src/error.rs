@@ -0,0 +1,23 @@
+//! Errors of the analysis entry points
+
+use std::fmt;
+
+/// Error returned when a series cannot be analyzed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// The series is constant, so all embedded points coincide
+    DegenerateSeries,
+    /// The delay-coordinates read samples after the reference time
+    NonCausal,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::DegenerateSeries => write!(f, "series is constant and its embedding collapses to a point"),
+            Error::NonCausal => write!(f, "delay-coordinates read samples after the reference time"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
@@ -0,0 +1,580 @@
+//! Estimation of embedding parameters
+
+use crate::{DelayCoordinates, Error, ForwardDelayCoordinates, NonUniformDelayCoordinates};
+use crate::analysis::{NeighborSearch, autocorrelation};
+use crate::dimension::{correlation_sums, fit_slope};
+use crate::metric::Metric;
+use crate::preprocess::{discretize, is_degenerate};
+
+/// Computes the time-delayed mutual information for the lags `0..=max_lag`
+///
+/// The series is discretized into `bins` equal-width bins and the mutual information between
+/// $x(t)$ and $x(t+\tau)$ is estimated from the joint histogram in nats.
+/// The $\tau$-th element of the returned vector is the value at lag $\tau$.
+pub fn ami_curve(series: &[f64], max_lag: usize, bins: usize) -> Vec<f64> {
+    let symbols = discretize(series, bins);
+    (0..=max_lag)
+        .map(|lag| {
+            if lag >= symbols.len() {
+                return 0.0;
+            }
+            let n = symbols.len() - lag;
+            let mut joint = vec![0usize; bins * bins];
+            let mut px = vec![0usize; bins];
+            let mut py = vec![0usize; bins];
+            for (&x, &y) in symbols.iter().zip(symbols[lag..].iter()) {
+                joint[x*bins+y] += 1;
+                px[x] += 1;
+                py[y] += 1;
+            }
+            joint.iter()
+                .enumerate()
+                .filter(|&(_, &count)| count > 0)
+                .map(|(k, &count)| {
+                    let pxy = count as f64 / n as f64;
+                    let denom = px[k/bins] as f64 * py[k%bins] as f64 / (n * n) as f64;
+                    pxy * (pxy / denom).ln()
+                })
+                .sum()
+        })
+        .collect()
+}
+
+/// Estimates the time delay by the first minimum of the time-delayed mutual information
+///
+/// If the curve of `ami_curve` has no local minimum up to `max_lag`,
+/// the lag of the smallest value is returned.
+/// Returns `Error::DegenerateSeries` if the series is constant.
+pub fn mutual_information_delay(series: &[f64], max_lag: usize, bins: usize) -> Result<usize, Error> {
+    if is_degenerate(series) {
+        return Err(Error::DegenerateSeries);
+    }
+    let ami = ami_curve(series, max_lag, bins);
+    let delay = (1..ami.len().saturating_sub(1))
+        .find(|&lag| ami[lag] < ami[lag-1] && ami[lag] <= ami[lag+1])
+        .unwrap_or_else(|| {
+            (1..ami.len())
+                .min_by(|&a, &b| ami[a].total_cmp(&ami[b]))
+                .unwrap_or(0)
+        });
+    Ok(delay)
+}
+
+/// Estimates the time delay by the average displacement criterion of Rosenstein, Collins and De Luca
+///
+/// The average displacement $S(\tau)$ of the two-dimensional embedding from the line of identity
+/// grows quickly for small delays, where the coordinates are redundant, and saturates for large
+/// delays, where they become irrelevant to each other. The delay where the slope of $S$ first
+/// falls below 40% of its initial slope balances the two and is returned. The initial slope is
+/// taken between the lags 1 and 2, so that the jump caused by observational noise at lag 1 does
+/// not dominate it.
+/// If it never does up to `max_lag`, `max_lag` is returned.
+/// Returns `Error::DegenerateSeries` if the series is constant.
+pub fn combined_delay(series: &[f64], max_lag: usize) -> Result<usize, Error> {
+    if is_degenerate(series) {
+        return Err(Error::DegenerateSeries);
+    }
+    let displacement = |lag: usize| {
+        let n = series.len().saturating_sub(lag);
+        if n == 0 {
+            return 0.0;
+        }
+        series.iter().zip(series[lag..].iter()).map(|(x, y)| (y - x).abs()).sum::<f64>() / n as f64
+    };
+    let mut prev = displacement(1);
+    let mut initial = None;
+    for lag in 2..=max_lag {
+        let current = displacement(lag);
+        let slope = current - prev;
+        match initial {
+            None => initial = Some(slope),
+            Some(initial) if slope < 0.4 * initial => return Ok(lag - 1),
+            Some(_) => {}
+        }
+        prev = current;
+    }
+    Ok(max_lag)
+}
+
+/// Assessment of a time delay against the decorrelation time of the series
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DelayQuality {
+    /// The delay is below a quarter of the decorrelation time, so the coordinates are redundant
+    Redundant,
+    /// The delay is comparable to the decorrelation time
+    Adequate,
+    /// The delay exceeds twice the decorrelation time, so the coordinates are nearly independent
+    OverStretched,
+}
+
+/// Decorrelation time of a series
+///
+/// This is the first lag where the autocorrelation falls below $1/e$.
+/// Returns `None` if it does not fall below up to half the length of the series.
+pub fn decorrelation_time(series: &[f64]) -> Option<usize> {
+    let acf = autocorrelation(series, series.len() / 2);
+    (1..acf.len()).find(|&lag| acf[lag] < (-1.0f64).exp())
+}
+
+/// Compares a time delay with the decorrelation time of the series
+///
+/// If the series does not decorrelate within half its length, any delay is considered adequate.
+/// Returns `Error::DegenerateSeries` if the series is constant.
+pub fn delay_diagnostics(series: &[f64], delay: usize) -> Result<DelayQuality, Error> {
+    if is_degenerate(series) {
+        return Err(Error::DegenerateSeries);
+    }
+    let quality = match decorrelation_time(series) {
+        Some(time) if delay > 2 * time => DelayQuality::OverStretched,
+        Some(time) if 4 * delay < time => DelayQuality::Redundant,
+        _ => DelayQuality::Adequate,
+    };
+    Ok(quality)
+}
+
+/// Computes the fraction of false nearest neighbors for the dimensions `1..=max_dim`
+///
+/// The $(d-1)$-th element is the fraction for dimension $d$. The nearest neighbor of a point in
+/// dimension $d$ is false if adding the $(d+1)$-th coordinate increases their distance by more
+/// than `rtol` times the distance in dimension $d$ (the first criterion of Kennel, Brown and
+/// Abarbanel). Pairs at distance zero are not counted.
+pub fn false_nearest_neighbors(series: &[f64], delay: usize, max_dim: usize, rtol: f64) -> Vec<f64> {
+    (1..=max_dim).map(|dimension| fnn_fraction(series, delay, dimension, rtol)).collect()
+}
+
+/// Fraction of false nearest neighbors in one dimension
+fn fnn_fraction(series: &[f64], delay: usize, dimension: usize, rtol: f64) -> f64 {
+    let search = NeighborSearch::new(Metric::Euclidean);
+    let extended = crate::embed(&ForwardDelayCoordinates { delay, dimension: dimension+1 }, series);
+    let points = extended.iter().map(|v| v[..dimension].to_vec()).collect::<Vec<_>>();
+    let mut counted = 0;
+    let mut false_neighbors = 0;
+    for i in 0..points.len() {
+        let j = match search.nearest(&points, i) {
+            Some(j) => j,
+            None => continue,
+        };
+        let dist = Metric::Euclidean.distance(&points[i], &points[j]);
+        if dist == 0.0 {
+            continue;
+        }
+        counted += 1;
+        if (extended[i][dimension] - extended[j][dimension]).abs() > rtol * dist {
+            false_neighbors += 1;
+        }
+    }
+    if counted == 0 { 0.0 } else { false_neighbors as f64 / counted as f64 }
+}
+
+/// Estimates the embedding dimension by the false nearest neighbors method
+///
+/// The smallest dimension up to `max_dim` whose fraction of false nearest neighbors is below
+/// `threshold` is returned, or `max_dim` if there is none.
+/// Returns `Error::DegenerateSeries` if the series is constant.
+pub fn fnn_dimension(series: &[f64], delay: usize, max_dim: usize, rtol: f64, threshold: f64) -> Result<usize, Error> {
+    if is_degenerate(series) {
+        return Err(Error::DegenerateSeries);
+    }
+    Ok((1..=max_dim)
+        .find(|&dimension| fnn_fraction(series, delay, dimension, rtol) < threshold)
+        .unwrap_or(max_dim))
+}
+
+/// Estimates the embedding dimension where the correlation dimension saturates
+///
+/// For each embedding dimension $m$ in `1..=max_dim`, the correlation dimension $D(m)$ is the
+/// least-squares slope of $\ln C(r)$ against $\ln r$ over `radii`, with pairs closer in time than
+/// `delay` excluded. The smallest $m$ with $|D(m+1) - D(m)| < 0.1 D(m)$ is returned, or `max_dim`
+/// if the estimate does not saturate.
+/// Returns `Error::DegenerateSeries` if the series is constant.
+///
+/// # Panics
+///
+/// Panics if there are less than two radii.
+pub fn saturation_dimension(series: &[f64], delay: usize, max_dim: usize, radii: &[f64]) -> Result<usize, Error> {
+    assert!(radii.len() >= 2, "at least two radii are needed to fit a slope");
+    if is_degenerate(series) {
+        return Err(Error::DegenerateSeries);
+    }
+    let log_r = radii.iter().map(|r| r.ln()).collect::<Vec<f64>>();
+    let estimate = |dimension: usize| {
+        let points = crate::embed(&ForwardDelayCoordinates { delay, dimension }, series);
+        let log_c = correlation_sums(&points, radii, Metric::Euclidean, delay)
+            .iter()
+            .map(|c| c.ln())
+            .collect::<Vec<f64>>();
+        fit_slope(&log_r, &log_c)
+    };
+    let mut current = estimate(1);
+    for dimension in 1..max_dim {
+        let next = estimate(dimension + 1);
+        if (next - current).abs() < 0.1 * current {
+            return Ok(dimension);
+        }
+        current = next;
+    }
+    Ok(max_dim)
+}
+
+/// Selects the delay that spreads the two-dimensional reconstruction the most
+///
+/// For each lag $\tau$ in `1..=max_lag`, the spread of the points $(x(t), x(t+\tau))$ is their
+/// generalized variance, the determinant of their covariance matrix, which is the squared area
+/// of their covariance ellipse up to a constant. It is small for a tiny delay, where the points
+/// lie on the diagonal, and for a delay where the series repeats itself. The first local maximum
+/// of the spread is returned, or the maximum if there is none, so huge delays are avoided.
+/// Returns `Error::DegenerateSeries` if the series is constant.
+pub fn geometric_delay(series: &[f64], max_lag: usize) -> Result<usize, Error> {
+    if is_degenerate(series) {
+        return Err(Error::DegenerateSeries);
+    }
+    let spread = (0..=max_lag.min(series.len().saturating_sub(2)))
+        .map(|lag| {
+            let (x, y) = (&series[..series.len()-lag], &series[lag..]);
+            let n = x.len() as f64;
+            let (mx, my) = (x.iter().sum::<f64>() / n, y.iter().sum::<f64>() / n);
+            let cov = |a: &[f64], ma: f64, b: &[f64], mb: f64| {
+                a.iter().zip(b.iter()).map(|(p, q)| (p - ma) * (q - mb)).sum::<f64>() / n
+            };
+            cov(x, mx, x, mx) * cov(y, my, y, my) - cov(x, mx, y, my).powi(2)
+        })
+        .collect::<Vec<f64>>();
+    let delay = (1..spread.len().saturating_sub(1))
+        .find(|&lag| spread[lag] > spread[lag-1] && spread[lag] >= spread[lag+1])
+        .unwrap_or_else(|| {
+            (1..spread.len())
+                .max_by(|&a, &b| spread[a].total_cmp(&spread[b]).then(b.cmp(&a)))
+                .unwrap_or(0)
+        });
+    Ok(delay)
+}
+
+/// Computes a contour of the space-time separation plot
+///
+/// The $\Delta t$-th element of the returned vector is the `percentile`-th percentile of the
+/// Euclidean distances between the embedded points $\Delta t$ steps apart, for
+/// $\Delta t$ in `0..=max_dt`. Temporal correlation shows as a rise of the contour with
+/// $\Delta t$. The contour is 0 where there is no pair.
+///
+/// # Panics
+///
+/// Panics if `percentile` is not in `[0, 100]`.
+pub fn space_time_separation<C>(coord: &C, slice: &[f64], max_dt: usize, percentile: f64) -> Vec<f64>
+where
+    C: DelayCoordinates,
+{
+    assert!((0.0..=100.0).contains(&percentile), "percentile must be in [0, 100]");
+    let points = crate::embed(coord, slice);
+    (0..=max_dt)
+        .map(|dt| {
+            let mut distances = points.iter()
+                .zip(points.iter().skip(dt))
+                .map(|(p, q)| Metric::Euclidean.distance(p, q))
+                .collect::<Vec<f64>>();
+            if distances.is_empty() {
+                return 0.0;
+            }
+            distances.sort_by(|a, b| a.total_cmp(b));
+            distances[(percentile / 100.0 * (distances.len() - 1) as f64).round() as usize]
+        })
+        .collect()
+}
+
+/// Estimates the Theiler window from the space-time separation plot
+///
+/// The `percentile` contour is computed up to a quarter of the number of embedded points, and
+/// its plateau level is the mean over the second half of that range. The returned window is the
+/// first temporal separation where the contour reaches 90% of the plateau level.
+/// It is 0 if the series has fewer than eight embedded points.
+///
+/// # Panics
+///
+/// Panics if `percentile` is not in `[0, 100]`.
+pub fn auto_theiler<C>(coord: &C, slice: &[f64], percentile: f64) -> usize
+where
+    C: DelayCoordinates,
+{
+    let n = slice.len().saturating_sub(coord.window_size()) + 1;
+    if coord.window_size() == 0 || slice.len() < coord.window_size() || n < 8 {
+        return 0;
+    }
+    let contour = space_time_separation(coord, slice, n / 4, percentile);
+    let tail = &contour[contour.len()/2..];
+    let plateau = tail.iter().sum::<f64>() / tail.len() as f64;
+    contour.iter()
+        .position(|&d| d >= 0.9 * plateau)
+        .unwrap_or(0)
+}
+
+/// Selects a non-uniform embedding greedily (PECUZAL-style)
+///
+/// Starting from the lag set $(0)$, the lag in `1..=max_lag` that minimizes the one-step
+/// nearest-neighbor prediction error of the embedding is added one at a time.
+/// The selection stops when `max_dim` lags are chosen, when the error vanishes or when the best
+/// candidate does not reduce the error by at least 10%.
+///
+/// This is a simplified variant of PECUZAL: the prediction error on the next sample stands in
+/// for the continuity and L-statistics of the original method.
+/// Returns `Error::DegenerateSeries` if the series is constant.
+pub fn pecuzal(series: &[f64], max_lag: usize, max_dim: usize) -> Result<NonUniformDelayCoordinates, Error> {
+    if is_degenerate(series) {
+        return Err(Error::DegenerateSeries);
+    }
+    let mut coord = NonUniformDelayCoordinates {
+        lags: vec![0],
+    };
+    let mut error = match prediction_error(series, &coord, max_lag) {
+        Some(error) => error,
+        None => return Ok(coord),
+    };
+    while coord.lags.len() < max_dim && error > 0.0 {
+        let best = (1..=max_lag)
+            .filter(|lag| !coord.lags.contains(lag))
+            .filter_map(|lag| {
+                let mut lags = coord.lags.clone();
+                lags.push(lag);
+                let candidate = NonUniformDelayCoordinates { lags };
+                prediction_error(series, &candidate, max_lag).map(|e| (e, candidate))
+            })
+            .min_by(|a, b| a.0.total_cmp(&b.0));
+        match best {
+            Some((e, candidate)) if e < 0.9 * error => {
+                error = e;
+                coord = candidate;
+            }
+            _ => break,
+        }
+    }
+    Ok(coord)
+}
+
+/// Selects a non-uniform embedding by maximizing derivatives on projection (MDOP)
+///
+/// Starting from the lag set $(0)$, each step finds the nearest neighbor $n(t)$ of every
+/// embedded point $v(t)$ outside a Theiler window of `max_lag` and scores each candidate lag
+/// $\tau$ in `1..=max_lag` by the geometric mean of the directional derivatives
+/// $|x(t-\tau) - x(n(t)-\tau)| / \|v(t) - v(n(t))\|$. The lag with the largest score is the one
+/// the current embedding is the most folded along. The selection stops when `max_dim` lags are
+/// chosen or when fewer than 1% of the neighbors are false along the best lag, i.e. their
+/// derivative exceeds 10.
+/// Every embedded point is referenced at the times from `max_lag` on, so all lag sets are
+/// compared on the same samples.
+/// Returns `Error::DegenerateSeries` if the series is constant.
+pub fn mdop(series: &[f64], max_lag: usize, max_dim: usize) -> Result<NonUniformDelayCoordinates, Error> {
+    if is_degenerate(series) {
+        return Err(Error::DegenerateSeries);
+    }
+    let mut lags = vec![0];
+    let times = (max_lag..series.len()).collect::<Vec<usize>>();
+    let search = NeighborSearch {
+        theiler: max_lag,
+        skip_zero: true,
+        ..NeighborSearch::new(Metric::Euclidean)
+    };
+    while lags.len() < max_dim {
+        let points = times.iter()
+            .map(|&t| lags.iter().map(|&lag| series[t-lag]).collect::<Vec<f64>>())
+            .collect::<Vec<_>>();
+        let pairs = (0..points.len())
+            .filter_map(|i| search.nearest(&points, i).map(|j| {
+                (times[i], times[j], Metric::Euclidean.distance(&points[i], &points[j]))
+            }))
+            .collect::<Vec<_>>();
+        if pairs.is_empty() {
+            break;
+        }
+        let derivative = |t: usize, u: usize, d: f64, lag: usize| (series[t-lag] - series[u-lag]).abs() / d;
+        let best = (1..=max_lag)
+            .filter(|lag| !lags.contains(lag))
+            .map(|lag| {
+                let logs = pairs.iter()
+                    .map(|&(t, u, d)| derivative(t, u, d, lag))
+                    .filter(|&beta| beta > 0.0)
+                    .map(f64::ln)
+                    .collect::<Vec<f64>>();
+                let score = if logs.is_empty() { f64::NEG_INFINITY } else { logs.iter().sum::<f64>() / logs.len() as f64 };
+                (score, lag)
+            })
+            .max_by(|a, b| a.0.total_cmp(&b.0).then(b.1.cmp(&a.1)));
+        let lag = match best {
+            Some((_, lag)) => lag,
+            None => break,
+        };
+        let false_neighbors = pairs.iter()
+            .filter(|&&(t, u, d)| derivative(t, u, d, lag) > 10.0)
+            .count();
+        if (false_neighbors as f64) < 0.01 * pairs.len() as f64 {
+            break;
+        }
+        lags.push(lag);
+    }
+    Ok(NonUniformDelayCoordinates {
+        lags,
+    })
+}
+
+/// Mean error of predicting the next sample by the successor of the nearest neighbor
+///
+/// Neighbors closer in time than `theiler` are excluded.
+/// Returns `None` if no point has an admissible neighbor.
+fn prediction_error<C>(series: &[f64], coord: &C, theiler: usize) -> Option<f64>
+where
+    C: DelayCoordinates,
+{
+    let ws = coord.window_size();
+    if series.len() <= ws {
+        return None;
+    }
+    let points = crate::embed(coord, &series[..series.len()-1]);
+    let next = |i: usize| series[i+ws];
+    let search = NeighborSearch {
+        theiler,
+        ..NeighborSearch::new(Metric::Euclidean)
+    };
+    let errors = (0..points.len())
+        .filter_map(|i| search.nearest(&points, i).map(|j| (next(i) - next(j)).abs()))
+        .collect::<Vec<f64>>();
+    if errors.is_empty() {
+        None
+    } else {
+        Some(errors.iter().sum::<f64>() / errors.len() as f64)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::Error;
+    use crate::estimate::{ami_curve, combined_delay, mutual_information_delay, pecuzal};
+    use crate::estimate::{DelayQuality, decorrelation_time, delay_diagnostics};
+    use crate::estimate::{false_nearest_neighbors, fnn_dimension, saturation_dimension};
+    use crate::estimate::{auto_theiler, geometric_delay, mdop, space_time_separation};
+    use crate::ForwardDelayCoordinates;
+    use crate::testutil::{sine, white_noise};
+
+    #[test]
+    fn test_pecuzal_sine() {
+        let series = sine(400, 23.7);
+        let coord = pecuzal(&series, 12, 4).unwrap();
+        assert_eq!(coord.lags.len(), 2);
+        assert_eq!(coord.lags[0], 0);
+        assert!((1..=12).contains(&coord.lags[1]));
+    }
+
+    #[test]
+    fn test_ami_curve_periodic() {
+        let series = sine(1000, 20.0);
+        let ami = ami_curve(&series, 45, 8);
+        assert_eq!(ami.len(), 46);
+        for lag in 0..25 {
+            assert!((ami[lag] - ami[lag+20]).abs() < 0.1);
+        }
+        assert!(ami[5] < ami[0]);
+        let delay = mutual_information_delay(&series, 45, 8).unwrap();
+        assert!((4..=6).contains(&delay));
+    }
+
+    #[test]
+    fn test_combined_delay() {
+        let series = sine(2000, 40.0).iter()
+            .zip(white_noise(2000, 7).iter())
+            .map(|(x, e)| x + 0.2 * e)
+            .collect::<Vec<f64>>();
+        let delay = combined_delay(&series, 40).unwrap();
+        let ami_delay = mutual_information_delay(&series, 40, 16).unwrap();
+        assert!((10..=20).contains(&delay));
+        assert!((5..=15).contains(&ami_delay));
+        assert!(delay >= ami_delay && delay <= 2 * ami_delay);
+    }
+
+    #[test]
+    fn test_degenerate_series() {
+        let series = vec![1.5; 200];
+        assert_eq!(mutual_information_delay(&series, 20, 8), Err(Error::DegenerateSeries));
+        assert_eq!(combined_delay(&series, 20), Err(Error::DegenerateSeries));
+        assert_eq!(pecuzal(&series, 10, 3), Err(Error::DegenerateSeries));
+    }
+
+    #[test]
+    fn test_delay_diagnostics() {
+        let series = sine(1000, 40.0);
+        let time = decorrelation_time(&series).unwrap();
+        assert!((6..=9).contains(&time));
+        assert_eq!(delay_diagnostics(&series, 1), Ok(DelayQuality::Redundant));
+        assert_eq!(delay_diagnostics(&series, 10), Ok(DelayQuality::Adequate));
+        assert_eq!(delay_diagnostics(&series, 30), Ok(DelayQuality::OverStretched));
+        assert_eq!(delay_diagnostics(&[2.0; 50], 3), Err(Error::DegenerateSeries));
+    }
+
+    #[test]
+    fn test_false_nearest_neighbors_sine() {
+        let series = sine(1000, 10.0 * std::f64::consts::PI);
+        let fractions = false_nearest_neighbors(&series, 8, 3, 10.0);
+        assert_eq!(fractions.len(), 3);
+        assert!(fractions[0] > 0.3);
+        assert!(fractions[1] < 0.01);
+        assert_eq!(fnn_dimension(&series, 8, 5, 10.0, 0.05), Ok(2));
+        assert_eq!(fnn_dimension(&[0.0; 100], 10, 5, 10.0, 0.05), Err(Error::DegenerateSeries));
+    }
+
+    #[test]
+    fn test_saturation_dimension_torus() {
+        // A quasi-periodic signal on a two-dimensional torus
+        let golden = (1.0 + 5.0f64.sqrt()) / 2.0;
+        let series = (0..1500)
+            .map(|t| {
+                let t = t as f64;
+                (t / 2.0).sin() + (t / (2.0 * golden)).sin()
+            })
+            .collect::<Vec<f64>>();
+        let radii = [0.3, 0.42, 0.6, 0.85, 1.2];
+        assert_eq!(saturation_dimension(&series, 3, 5, &radii), Ok(3));
+        assert_eq!(saturation_dimension(&[1.0; 50], 3, 5, &radii), Err(Error::DegenerateSeries));
+    }
+
+    #[test]
+    fn test_auto_theiler() {
+        let noise = white_noise(3000, 17);
+        let mut series = vec![0.0];
+        for e in noise.iter() {
+            let last = series[series.len()-1];
+            series.push(0.97 * last + e);
+        }
+        let coord = ForwardDelayCoordinates {
+            delay: 1,
+            dimension: 2,
+        };
+        let contour = space_time_separation(&coord, &series, 100, 50.0);
+        assert_eq!(contour[0], 0.0);
+        assert!(contour[1] < contour[30]);
+        let theiler = auto_theiler(&coord, &series, 50.0);
+        assert!((10..=100).contains(&theiler), "theiler = {}", theiler);
+        assert!(auto_theiler(&coord, &noise, 50.0) <= 2);
+    }
+
+    #[test]
+    fn test_mdop_henon() {
+        let (mut x, mut y) = (0.1, 0.0);
+        let series = (0..2100)
+            .map(|_| {
+                let next = 1.0 - 1.4 * x * x + y;
+                y = 0.3 * x;
+                x = next;
+                x
+            })
+            .skip(100)
+            .collect::<Vec<f64>>();
+        let coord = mdop(&series, 5, 6).unwrap();
+        assert_eq!(coord.lags[0], 0);
+        assert!((2..=3).contains(&coord.lags.len()));
+        assert!(coord.lags.contains(&1));
+        assert_eq!(mdop(&[1.0; 50], 5, 6), Err(Error::DegenerateSeries));
+    }
+
+    #[test]
+    fn test_geometric_delay() {
+        let series = sine(2000, 40.0);
+        assert_eq!(geometric_delay(&series, 60), Ok(10));
+        assert_eq!(geometric_delay(&series, 5), Ok(5));
+        assert_eq!(geometric_delay(&[1.0; 50], 10), Err(Error::DegenerateSeries));
+    }
+}
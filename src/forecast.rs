@@ -0,0 +1,137 @@
+//! Forecasting in delay-coordinates
+//!
+//! Predictors read only the past of the reference time, so they reject non-causal
+//! delay-coordinates with `Error::NonCausal`.
+
+use std::ops::RangeInclusive;
+
+use crate::{DelayCoordinates, Error, ForwardDelayCoordinates, embed_with_targets, require_causal};
+use crate::linalg::least_squares;
+use crate::metric::Metric;
+
+/// Predicts the target of `query` by the mean target of its `k` nearest library points
+fn analog_predict(library: &[Vec<f64>], targets: &[f64], query: &[f64], k: usize) -> f64 {
+    let mut nearest = library.iter()
+        .map(|p| Metric::Euclidean.distance(p, query))
+        .zip(targets.iter().cloned())
+        .collect::<Vec<_>>();
+    nearest.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let k = k.min(nearest.len());
+    nearest[..k].iter().map(|&(_, y)| y).sum::<f64>() / k as f64
+}
+
+/// Root-mean-square error of the analog forecast validated on the second half of the series
+///
+/// The first half of the series is the library and each window of the second half is predicted
+/// from the `k` nearest windows of the library, so no window is predicted from itself.
+/// Returns `None` if either half has no window with its target.
+fn validation_error<C>(series: &[f64], coord: &C, k: usize, horizon: usize) -> Option<f64>
+where
+    C: DelayCoordinates,
+{
+    let (train, test) = series.split_at(series.len() / 2);
+    let (library, targets) = embed_with_targets(coord, train, horizon);
+    let (queries, actual) = embed_with_targets(coord, test, horizon);
+    if library.is_empty() || queries.is_empty() {
+        return None;
+    }
+    let sum = queries.iter()
+        .zip(actual.iter())
+        .map(|(q, y)| (analog_predict(&library, &targets, q, k) - y).powi(2))
+        .sum::<f64>();
+    Some((sum / queries.len() as f64).sqrt())
+}
+
+/// Selects the number of neighbors of the analog forecast by validation
+///
+/// The `k` in `k_range` minimizing the forecast error on the second half of the series, predicted
+/// from the first half, is returned. The smallest one is returned on ties, and `k_range.start()`
+/// is returned if the series is too short to validate.
+/// Returns `Error::NonCausal` if the delay-coordinates are not causal.
+///
+/// # Panics
+///
+/// Panics if `k_range` is empty or contains zero.
+pub fn optimal_k<C>(series: &[f64], coord: &C, k_range: RangeInclusive<usize>, horizon: usize) -> Result<usize, Error>
+where
+    C: DelayCoordinates,
+{
+    require_causal(coord)?;
+    assert!(!k_range.is_empty() && *k_range.start() > 0, "k_range must be a non-empty range of positive numbers");
+    let start = *k_range.start();
+    let best = k_range
+        .filter_map(|k| validation_error(series, coord, k, horizon).map(|e| (e, k)))
+        .min_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)))
+        .map_or(start, |(_, k)| k);
+    Ok(best)
+}
+
+/// Fits a linear autoregressive model in forward delay-coordinates by least squares
+///
+/// The next sample after each window is modeled as $c + \sum_i a_i v_i$, where $v_i$ is the
+/// $i$-th coordinate of the window. The returned coefficients are $(a_0, \ldots, a_{d-1}, c)$
+/// and the returned predictions are the fitted one-step predictions of the windows in order.
+/// Both are empty if the series is too short or the model is singular, e.g. for a constant series.
+pub fn linear_ar(series: &[f64], delay: usize, dimension: usize) -> (Vec<f64>, Vec<f64>) {
+    let coord = ForwardDelayCoordinates {
+        delay,
+        dimension,
+    };
+    let (mut rows, targets) = embed_with_targets(&coord, series, 1);
+    for row in rows.iter_mut() {
+        row.push(1.0);
+    }
+    match least_squares(&rows, &targets) {
+        Some(coefficients) => {
+            let predictions = rows.iter()
+                .map(|row| row.iter().zip(coefficients.iter()).map(|(x, c)| x * c).sum())
+                .collect();
+            (coefficients, predictions)
+        }
+        None => (Vec::new(), Vec::new()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{Error, ForwardDelayCoordinates, MixedDelayCoordinates};
+    use crate::forecast::{linear_ar, optimal_k};
+    use crate::testutil::{sine, white_noise};
+
+    #[test]
+    fn test_optimal_k() {
+        let series = sine(600, 10.0 * std::f64::consts::PI).iter()
+            .zip(white_noise(600, 4).iter())
+            .map(|(x, e)| x + 0.3 * e)
+            .collect::<Vec<f64>>();
+        let coord = ForwardDelayCoordinates {
+            delay: 8,
+            dimension: 2,
+        };
+        let k = optimal_k(&series, &coord, 1..=30, 1).unwrap();
+        assert!((3..30).contains(&k));
+
+        let centered = MixedDelayCoordinates {
+            lags: vec![-1, 0, 1],
+        };
+        assert_eq!(optimal_k(&series, &centered, 1..=5, 1), Err(Error::NonCausal));
+    }
+
+    #[test]
+    fn test_linear_ar() {
+        let noise = white_noise(5000, 12);
+        let mut series = vec![0.0, 0.0];
+        for e in noise.iter() {
+            let n = series.len();
+            series.push(0.6 * series[n-1] - 0.3 * series[n-2] + 0.1 * e);
+        }
+        let (coefficients, predictions) = linear_ar(&series, 1, 2);
+        assert_eq!(coefficients.len(), 3);
+        assert!((coefficients[0] - 0.6).abs() < 0.03);
+        assert!((coefficients[1] + 0.3).abs() < 0.03);
+        assert!(coefficients[2].abs() < 0.01);
+        assert_eq!(predictions.len(), series.len() - 2);
+        let (coefficients, predictions) = linear_ar(&[1.0; 20], 1, 2);
+        assert!(coefficients.is_empty() && predictions.is_empty());
+    }
+}
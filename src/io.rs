@@ -0,0 +1,208 @@
+//! Export of embedded trajectories
+
+use std::fs::File;
+use std::io::{BufWriter, Error, ErrorKind, Result, Write};
+use std::path::Path;
+
+use crate::DelayCoordinates;
+#[cfg(feature = "image")]
+use crate::metric::Metric;
+
+#[cfg(feature = "image")]
+mod png;
+
+/// Writes the embedded points as a legacy VTK PolyData point cloud
+///
+/// The embedding dimension must be at most 3 and missing coordinates are padded with zeros.
+/// Each point is also written as a vertex cell so that viewers such as ParaView render it.
+pub fn write_vtk_points<P, C>(path: P, coord: &C, slice: &[f64]) -> Result<()>
+where
+    P: AsRef<Path>,
+    C: DelayCoordinates,
+{
+    if coord.dimension() > 3 {
+        return Err(Error::new(ErrorKind::InvalidInput, "VTK points require dimension at most 3"));
+    }
+    let points = crate::embed(coord, slice);
+    let mut writer = BufWriter::new(File::create(path)?);
+    writeln!(writer, "# vtk DataFile Version 3.0")?;
+    writeln!(writer, "delay-coordinates embedding")?;
+    writeln!(writer, "ASCII")?;
+    writeln!(writer, "DATASET POLYDATA")?;
+    writeln!(writer, "POINTS {} double", points.len())?;
+    for p in points.iter() {
+        let xyz = (0..3).map(|k| p.get(k).cloned().unwrap_or(0.0)).collect::<Vec<f64>>();
+        writeln!(writer, "{} {} {}", xyz[0], xyz[1], xyz[2])?;
+    }
+    writeln!(writer, "VERTICES {} {}", points.len(), 2 * points.len())?;
+    for i in 0..points.len() {
+        writeln!(writer, "1 {}", i)?;
+    }
+    writer.flush()
+}
+
+/// Writes the recurrence plot of the embedded series as a black-and-white PNG image
+///
+/// The pixel in the `i`-th column and the `j`-th row from the bottom is black if the `i`-th and
+/// the `j`-th points are recurrent, so the image is square with a side of the number of points.
+#[cfg(feature = "image")]
+pub fn write_recurrence_png<P, C>(path: P, coord: &C, slice: &[f64], threshold: f64, metric: Metric) -> Result<()>
+where
+    P: AsRef<Path>,
+    C: DelayCoordinates,
+{
+    let matrix = crate::recurrence::recurrence_matrix(coord, slice, threshold, metric);
+    let rows = matrix.iter()
+        .rev()
+        .map(|row| row.iter().map(|&r| if r { 0 } else { 255 }).collect())
+        .collect::<Vec<Vec<u8>>>();
+    let mut writer = BufWriter::new(File::create(path)?);
+    writer.write_all(&png::encode_gray(&rows))?;
+    writer.flush()
+}
+
+/// Writes the embedded trajectory as a Wavefront OBJ polyline
+///
+/// Each embedded point is written as a vertex and consecutive points are joined by a single
+/// polyline element, so 3D viewers draw the trajectory as a curve.
+/// The embedding dimension must be 3.
+#[cfg(feature = "obj")]
+pub fn write_obj_trajectory<P, C>(path: P, coord: &C, slice: &[f64]) -> Result<()>
+where
+    P: AsRef<Path>,
+    C: DelayCoordinates,
+{
+    if coord.dimension() != 3 {
+        return Err(Error::new(ErrorKind::InvalidInput, "OBJ trajectory requires dimension 3"));
+    }
+    let points = crate::embed(coord, slice);
+    let mut writer = BufWriter::new(File::create(path)?);
+    writeln!(writer, "# delay-coordinates embedding")?;
+    for p in points.iter() {
+        writeln!(writer, "v {} {} {}", p[0], p[1], p[2])?;
+    }
+    if points.len() >= 2 {
+        write!(writer, "l")?;
+        // OBJ vertex indices start from 1
+        for i in 1..=points.len() {
+            write!(writer, " {}", i)?;
+        }
+        writeln!(writer)?;
+    }
+    writer.flush()
+}
+
+/// Embeds the series as a point cloud for topological data analysis
+///
+/// This is the trajectory matrix, one embedded vector in each row, under a name that TDA users
+/// look for. It can be passed as is to a Vietoris–Rips construction.
+pub fn embed_point_cloud<C>(coord: &C, slice: &[f64]) -> Vec<Vec<f64>>
+where
+    C: DelayCoordinates,
+{
+    crate::embed(coord, slice)
+}
+
+/// Subsamples a point cloud so that any two kept points are more than `theiler` steps apart
+///
+/// Every `(theiler + 1)`-th point is kept starting from the first one, which removes the
+/// temporally correlated neighbors that would otherwise dominate the small-scale topology.
+pub fn theiler_subsample(points: &[Vec<f64>], theiler: usize) -> Vec<Vec<f64>> {
+    points.iter().step_by(theiler + 1).cloned().collect()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::ForwardDelayCoordinates;
+    use crate::io::{embed_point_cloud, theiler_subsample, write_vtk_points};
+
+    #[test]
+    fn test_write_vtk_points() {
+        let data = (0..5).map(|n| n as f64).collect::<Vec<f64>>();
+        let coord = ForwardDelayCoordinates {
+            delay: 1,
+            dimension: 2,
+        };
+        let path = std::env::temp_dir().join("delay_coord_test_write_vtk_points.vtk");
+        write_vtk_points(&path, &coord, &data).unwrap();
+        let text = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let lines = text.lines().collect::<Vec<_>>();
+        assert_eq!(lines[0], "# vtk DataFile Version 3.0");
+        assert_eq!(lines[3], "DATASET POLYDATA");
+        assert_eq!(lines[4], "POINTS 4 double");
+        assert_eq!(lines[5], "1 0 0");
+        assert_eq!(lines[9], "VERTICES 4 8");
+        assert_eq!(lines.len(), 14);
+
+        let coord = ForwardDelayCoordinates {
+            delay: 1,
+            dimension: 4,
+        };
+        assert!(write_vtk_points(&path, &coord, &data).is_err());
+    }
+
+    #[test]
+    fn test_embed_point_cloud() {
+        let data = (0..20).map(|n| n as f64).collect::<Vec<f64>>();
+        let coord = ForwardDelayCoordinates {
+            delay: 2,
+            dimension: 3,
+        };
+        let cloud = embed_point_cloud(&coord, &data);
+        assert_eq!(cloud.len(), 16);
+        assert!(cloud.iter().all(|p| p.len() == 3));
+        let sparse = theiler_subsample(&cloud, 4);
+        assert_eq!(sparse.len(), 4);
+        assert_eq!(sparse[1], vec![9.0, 7.0, 5.0]);
+        assert_eq!(theiler_subsample(&cloud, 0), cloud);
+    }
+
+    #[cfg(feature = "image")]
+    #[test]
+    fn test_write_recurrence_png() {
+        use crate::io::write_recurrence_png;
+        use crate::metric::Metric;
+
+        let coord = ForwardDelayCoordinates {
+            delay: 1,
+            dimension: 2,
+        };
+        let data = (0..12).map(|n| (n as f64).sin()).collect::<Vec<f64>>();
+        let path = std::env::temp_dir().join("delay_coord_test_write_recurrence.png");
+        write_recurrence_png(&path, &coord, &data, 0.5, Metric::Euclidean).unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(&bytes[..8], &[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A]);
+        assert_eq!(&bytes[12..16], b"IHDR");
+        assert_eq!(u32::from_be_bytes([bytes[16], bytes[17], bytes[18], bytes[19]]), 11);
+        assert_eq!(u32::from_be_bytes([bytes[20], bytes[21], bytes[22], bytes[23]]), 11);
+        assert_eq!(&bytes[bytes.len()-8..bytes.len()-4], b"IEND");
+    }
+
+    #[cfg(feature = "obj")]
+    #[test]
+    fn test_write_obj_trajectory() {
+        use crate::io::write_obj_trajectory;
+
+        let data = (0..8).map(|n| n as f64).collect::<Vec<f64>>();
+        let coord = ForwardDelayCoordinates {
+            delay: 1,
+            dimension: 3,
+        };
+        let path = std::env::temp_dir().join("delay_coord_test_write_obj_trajectory.obj");
+        write_obj_trajectory(&path, &coord, &data).unwrap();
+        let text = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let vertices = text.lines().filter(|line| line.starts_with("v ")).collect::<Vec<_>>();
+        let lines = text.lines().filter(|line| line.starts_with("l ")).collect::<Vec<_>>();
+        assert_eq!(vertices.len(), 6);
+        assert_eq!(vertices[0], "v 2 1 0");
+        assert_eq!(lines, vec!["l 1 2 3 4 5 6"]);
+        let plane = ForwardDelayCoordinates {
+            delay: 1,
+            dimension: 2,
+        };
+        assert!(write_obj_trajectory(&path, &plane, &data).is_err());
+    }
+}
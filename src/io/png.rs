@@ -0,0 +1,87 @@
+//! Minimal PNG encoder for 8-bit grayscale images
+//!
+//! The image data is stored in uncompressed deflate blocks, which every PNG reader accepts.
+
+/// Largest payload of a stored deflate block
+const MAX_BLOCK: usize = 65535;
+
+/// Encodes an image given by rows of gray levels
+///
+/// All rows must have the same length.
+pub(crate) fn encode_gray(rows: &[Vec<u8>]) -> Vec<u8> {
+    let height = rows.len() as u32;
+    let width = rows.first().map_or(0, |row| row.len()) as u32;
+    let mut ihdr = Vec::with_capacity(13);
+    ihdr.extend_from_slice(&width.to_be_bytes());
+    ihdr.extend_from_slice(&height.to_be_bytes());
+    // Bit depth 8, grayscale, deflate, adaptive filtering, no interlace
+    ihdr.extend_from_slice(&[8, 0, 0, 0, 0]);
+    // Each scanline starts with the filter type 0 (none)
+    let raw = rows.iter()
+        .flat_map(|row| std::iter::once(0).chain(row.iter().cloned()))
+        .collect::<Vec<u8>>();
+
+    let mut png = vec![0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+    write_chunk(&mut png, b"IHDR", &ihdr);
+    write_chunk(&mut png, b"IDAT", &zlib_stored(&raw));
+    write_chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+fn write_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    let crc = crc32(&png[start..]);
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+/// Wraps the data in a zlib stream of stored deflate blocks
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let mut out = vec![0x78, 0x01];
+    let blocks = data.chunks(MAX_BLOCK).collect::<Vec<_>>();
+    if blocks.is_empty() {
+        out.extend_from_slice(&[1, 0, 0, 0xFF, 0xFF]);
+    }
+    for (k, block) in blocks.iter().enumerate() {
+        out.push(if k + 1 == blocks.len() { 1 } else { 0 });
+        let len = block.len() as u16;
+        out.extend_from_slice(&len.to_le_bytes());
+        out.extend_from_slice(&(!len).to_le_bytes());
+        out.extend_from_slice(block);
+    }
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 { 0xEDB8_8320 ^ (crc >> 1) } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in data {
+        a = (a + byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
+
+#[cfg(test)]
+mod test {
+    use crate::io::png::{adler32, crc32};
+
+    #[test]
+    fn test_checksums() {
+        assert_eq!(crc32(b"IEND"), 0xAE42_6082);
+        assert_eq!(adler32(b"Wikipedia"), 0x11E6_0398);
+    }
+}
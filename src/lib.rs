@@ -1,4 +1,25 @@
+pub mod analysis;
+pub mod dataset;
+pub mod dimension;
+pub mod epoch;
+mod error;
+pub mod estimate;
+pub mod forecast;
+pub mod io;
+mod linalg;
+pub mod metric;
+pub mod preprocess;
+pub mod recurrence;
+mod rng;
+pub mod symbolic;
+
+#[cfg(test)]
+mod testutil;
+
+pub use crate::error::Error;
+
 use std::ops::Index;
+use std::sync::Arc;
 
 /// Delay-coordinates
 ///
@@ -13,12 +34,116 @@ pub trait DelayCoordinates where Self: Sized {
     /// Maps an index in the delay-coordinates into the index of the underlying series
     fn map_coord(&self, index: usize) -> Option<usize>;
 
+    /// Length of the series needed to produce `n_windows` windows
+    ///
+    /// It is zero for zero windows.
+    fn len_for_windows(&self, n_windows: usize) -> usize {
+        if n_windows == 0 { 0 } else { self.window_size() + n_windows - 1 }
+    }
+
+    /// Returns `true` if no coordinate reads a sample after the reference time
+    ///
+    /// The reference time is the time a prediction is made from. The delay-coordinates of this
+    /// crate take the last sample of the window as the reference time, so they are causal
+    /// unless they override this method.
+    fn is_causal(&self) -> bool {
+        true
+    }
+
+    /// Positions in the window of all coordinates
+    fn map_coords(&self) -> Vec<usize> {
+        (0..self.dimension()).filter_map(|index| self.map_coord(index)).collect()
+    }
+
+    /// Positions in the window of all coordinates as an array
+    ///
+    /// This is useful when the dimension is known at compile time, e.g. to unroll inner loops.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `N` is not the dimension.
+    fn coord_offsets<const N: usize>(&self) -> [usize; N] {
+        assert_eq!(N, self.dimension(), "array length must equal the dimension");
+        std::array::from_fn(|index| self.map_coord(index).unwrap())
+    }
+
+    /// Describes the provenance of each coordinate
+    fn describe(&self) -> Vec<CoordInfo> {
+        let last = self.window_size().saturating_sub(1);
+        (0..self.dimension())
+            .filter_map(|index| self.map_coord(index))
+            .map(|pos| CoordInfo {
+                channel: None,
+                lag: last-pos,
+                kind: CoordKind::Delay,
+            })
+            .collect()
+    }
+
+    /// Delay-coordinates of the time-reversed series
+    ///
+    /// Each window is embedded as if the series ran backward, so the past and future lags are
+    /// swapped: the coordinate that reads the last sample of the window reads the first one.
+    fn time_reversed(&self) -> TimeReversed<Self>
+    where
+        Self: Clone,
+    {
+        TimeReversed {
+            inner: self.clone(),
+        }
+    }
+
+    /// Iterates over the views of the sliding windows of `slice`
+    ///
+    /// The iterator is lazy, so `mapping_iter(slice).take(n)` maps only the first `n` windows.
     fn mapping_iter<'a, T>(&'a self, slice: &'a [T]) -> MappingIter<'a, T, Self> {
-        MappingIter {
-            coord: &self,
-            slice: slice,
+        self.windowed_views(slice, Sliding::new())
+    }
+
+    /// Iterates over the views of the windows of `slice` whose coordinates contain no NaN
+    ///
+    /// Only the samples mapped to coordinates are checked.
+    fn mapping_iter_skip_nan<'a>(&'a self, slice: &'a [f64]) -> SkipNanMappingIter<'a, Self> {
+        SkipNanMappingIter {
+            iter: self.mapping_iter(slice),
         }
     }
+
+    /// Iterates over the views of the windows of `slice` chosen by `windower`
+    ///
+    /// `mapping_iter` is this method with the `Sliding` windower.
+    fn windowed_views<'a, T, W>(&'a self, slice: &'a [T], windower: W) -> WindowedViews<'a, T, Self, W>
+    where
+        W: Windower,
+    {
+        WindowedViews {
+            coord: self,
+            slice,
+            windower,
+        }
+    }
+}
+
+/// Kind of a coordinate in delay-coordinates
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CoordKind {
+    /// A sample of the series taken at a lag
+    Delay,
+    /// A sample of the series taken after the reference time
+    Lead,
+}
+
+/// Description of a coordinate in delay-coordinates
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoordInfo {
+    /// Channel of multivariate samples the coordinate is taken from, or `None` for all channels
+    pub channel: Option<usize>,
+    /// Lag from the reference time, or the lead after it for `CoordKind::Lead`
+    ///
+    /// The reference time is the last sample of the window unless the delay-coordinates read
+    /// samples after it.
+    pub lag: usize,
+    pub kind: CoordKind,
 }
 
 /// Forward Delay-coordinates
@@ -60,193 +185,1084 @@ impl DelayCoordinates for ForwardDelayCoordinates {
     }
 }
 
-#[derive(Debug, Clone)]
-pub struct MappingIter<'a, T, C> {
-    coord: &'a C,
-    slice: &'a [T],
-}
+impl ForwardDelayCoordinates {
+    /// Iterates over the views of the windows of `slice` with their anchor times
+    ///
+    /// The samples are taken at the rate `fs` from the time `t0`, so the anchor time of the
+    /// window starting at the index $s$ is $t_0 + s / f_s$.
+    pub fn timed_views_rate<'a, T>(&'a self, slice: &'a [T], fs: f64, t0: f64)
+        -> impl Iterator<Item = (f64, DelayMappedView<'a, T, Self>)> + 'a
+    {
+        self.mapping_iter(slice)
+            .enumerate()
+            .map(move |(start, view)| (t0 + start as f64 / fs, view))
+    }
 
-impl<'a, T, C> Iterator for MappingIter<'a, T, C>
-where
-    C: DelayCoordinates,
-{
-    type Item = DelayMappedView<'a, T, C>;
+    /// Computes the delay-coordinates in coordinate-major layout
+    ///
+    /// The returned vector holds all values of coordinate 0, then all values of coordinate 1,
+    /// and so on. The second and third elements are the number of windows and the dimension.
+    pub fn to_transposed_flat<T: Clone>(&self, slice: &[T]) -> (Vec<T>, usize, usize) {
+        let rows = (slice.len() + 1).saturating_sub(self.window_size());
+        let cols = self.dimension;
+        let mut data = Vec::with_capacity(rows * cols);
+        if rows == 0 {
+            return (data, rows, cols);
+        }
+        for index in 0..cols {
+            let pos = self.map_coord(index).unwrap();
+            data.extend_from_slice(&slice[pos..pos+rows]);
+        }
+        (data, rows, cols)
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.slice.len() < self.coord.window_size() {
-            None
-        } else {
-            let view = DelayMappedView {
-                coord: self.coord,
-                slice: &self.slice[0..self.coord.window_size()],
-            };
-            self.slice = &self.slice[1..];
-            Some(view)
+    /// Builds the Hankel trajectory matrix of `slice`
+    ///
+    /// Each row is a whole window of `window_size` consecutive samples in time order, including
+    /// the samples between the delayed coordinates, so the element `[i][j]` is `slice[i+j]` and
+    /// the anti-diagonals are constant.
+    pub fn hankel_matrix<T: Clone>(&self, slice: &[T]) -> Vec<Vec<T>> {
+        let ws = self.window_size();
+        if ws == 0 {
+            return Vec::new();
         }
+        slice.windows(ws).map(|window| window.to_vec()).collect()
     }
 }
 
-impl<'a, T, C> MappingIter<'a, T, C>
-where
-    T: Clone,
-    C: DelayCoordinates,
-{
-    pub fn to_vec(self) -> MappingIterToVec<'a, T, C> {
-        MappingIterToVec {
-            iter: self,
-        }
+/// Non-uniform delay-coordinates
+///
+/// Non-uniform delay-coordinates with lags $(l_0, l_1, \ldots, l_{d-1})$ is defined for a series
+/// $x(t)$ as below:
+///
+/// $$
+/// (x(t-l_0), x(t-l_1), \ldots, x(t-l_{d-1})),
+/// $$
+///
+/// where $t$ is the last index of the window. `ForwardDelayCoordinates` corresponds to the lags
+/// $(0, m, \ldots, (d-1)m)$.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NonUniformDelayCoordinates {
+    pub lags: Vec<usize>,
+}
+
+impl NonUniformDelayCoordinates {
+    #[inline]
+    fn max_lag(&self) -> usize {
+        self.lags.iter().cloned().max().unwrap_or(0)
     }
 }
 
-impl<'a, T, C> MappingIter<'a, T, C>
-where
-    T: std::iter::IntoIterator + Clone,
-    C: DelayCoordinates,
-{
-    pub fn to_flatten_vec(self) -> MappingIterToFlattenVec<'a, T, C> {
-        MappingIterToFlattenVec {
-            iter: self,
+impl DelayCoordinates for NonUniformDelayCoordinates {
+    /// Smallest positive lag of the delay-coordinates
+    #[inline]
+    fn delay(&self) -> usize {
+        self.lags.iter().cloned().filter(|&lag| lag > 0).min().unwrap_or(0)
+    }
+
+    #[inline]
+    fn dimension(&self) -> usize { self.lags.len() }
+
+    #[inline]
+    fn window_size(&self) -> usize {
+        if self.lags.is_empty() { 0 } else { self.max_lag()+1 }
+    }
+
+    #[inline]
+    fn map_coord(&self, index: usize) -> Option<usize> {
+        self.lags.get(index).map(|lag| self.max_lag()-lag)
+    }
+}
+
+/// Non-uniform delay-coordinates with random lags
+///
+/// The lags are `dimension` distinct lags drawn uniformly from $0, \ldots, \text{max\_lag}$
+/// and sorted in increasing order. They are fixed by the seed, so an analysis can be compared
+/// against the same random-lag baseline across runs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RandomDelayCoordinates {
+    inner: NonUniformDelayCoordinates,
+}
+
+impl RandomDelayCoordinates {
+    /// Draws the lags from the seed
+    ///
+    /// # Panics
+    ///
+    /// Panics if `dimension` is larger than `max_lag + 1`.
+    pub fn new(dimension: usize, max_lag: usize, seed: u64) -> Self {
+        assert!(dimension <= max_lag + 1, "dimension must not exceed the number of lags");
+        let mut rng = rng::Xorshift::new(seed);
+        let mut candidates = (0..=max_lag).collect::<Vec<usize>>();
+        for i in 0..dimension {
+            let j = i + rng.below(candidates.len() - i);
+            candidates.swap(i, j);
         }
+        let mut lags = candidates[..dimension].to_vec();
+        lags.sort_unstable();
+        RandomDelayCoordinates {
+            inner: NonUniformDelayCoordinates {
+                lags,
+            },
+        }
+    }
+
+    /// Lags of the delay-coordinates
+    pub fn lags(&self) -> &[usize] {
+        &self.inner.lags
     }
 }
 
-#[derive(Debug, Clone)]
-pub struct MappingIterToVec<'a, T, C> {
-    iter: MappingIter<'a, T, C>,
+impl DelayCoordinates for RandomDelayCoordinates {
+    #[inline]
+    fn delay(&self) -> usize { self.inner.delay() }
+
+    #[inline]
+    fn dimension(&self) -> usize { self.inner.dimension() }
+
+    #[inline]
+    fn window_size(&self) -> usize { self.inner.window_size() }
+
+    #[inline]
+    fn map_coord(&self, index: usize) -> Option<usize> { self.inner.map_coord(index) }
 }
 
-impl<'a, T, C> Iterator for MappingIterToVec<'a, T, C>
+/// Delay-coordinates with signed lags
+///
+/// Mixed delay-coordinates with lags $(l_0, l_1, \ldots, l_{d-1})$ is defined for a series
+/// $x(t)$ as below:
+///
+/// $$
+/// (x(t-l_0), x(t-l_1), \ldots, x(t-l_{d-1})),
+/// $$
+///
+/// where $t$ is the reference time. A negative lag reads a sample after the reference time,
+/// e.g. the lags $(-m, 0, m)$ give centered coordinates. The window spans from the largest lag
+/// to the smallest one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MixedDelayCoordinates {
+    pub lags: Vec<isize>,
+}
+
+impl MixedDelayCoordinates {
+    #[inline]
+    fn max_lag(&self) -> isize {
+        self.lags.iter().cloned().max().unwrap_or(0)
+    }
+
+    #[inline]
+    fn min_lag(&self) -> isize {
+        self.lags.iter().cloned().min().unwrap_or(0)
+    }
+}
+
+impl DelayCoordinates for MixedDelayCoordinates {
+    /// Smallest positive difference between two lags
+    fn delay(&self) -> usize {
+        let mut lags = self.lags.clone();
+        lags.sort_unstable();
+        lags.windows(2)
+            .map(|w| (w[1]-w[0]) as usize)
+            .filter(|&d| d > 0)
+            .min()
+            .unwrap_or(0)
+    }
+
+    #[inline]
+    fn dimension(&self) -> usize { self.lags.len() }
+
+    #[inline]
+    fn window_size(&self) -> usize {
+        if self.lags.is_empty() { 0 } else { (self.max_lag()-self.min_lag()) as usize + 1 }
+    }
+
+    #[inline]
+    fn map_coord(&self, index: usize) -> Option<usize> {
+        self.lags.get(index).map(|lag| (self.max_lag()-lag) as usize)
+    }
+
+    /// Returns `true` if no lag is negative
+    #[inline]
+    fn is_causal(&self) -> bool {
+        self.lags.iter().all(|&lag| lag >= 0)
+    }
+
+    /// Describes the negative lags as leads after the reference time
+    fn describe(&self) -> Vec<CoordInfo> {
+        self.lags.iter()
+            .map(|&lag| CoordInfo {
+                channel: None,
+                lag: lag.unsigned_abs(),
+                kind: if lag < 0 { CoordKind::Lead } else { CoordKind::Delay },
+            })
+            .collect()
+    }
+}
+
+/// Checks that the delay-coordinates are causal
+///
+/// Predictors call this at construction, so that an online prediction never reads the future.
+/// Returns `Error::NonCausal` otherwise.
+pub fn require_causal<C>(coord: &C) -> Result<(), Error>
 where
-    T: Clone,
     C: DelayCoordinates,
 {
-    type Item = Vec<T>;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        self.iter.next().map(|view| view.to_vec())
+    if coord.is_causal() {
+        Ok(())
+    } else {
+        Err(Error::NonCausal)
     }
 }
 
+/// Time-reversed delay-coordinates
+///
+/// This struct is created by `DelayCoordinates::time_reversed`.
 #[derive(Debug, Clone)]
-pub struct MappingIterToFlattenVec<'a, T, C> {
-    iter: MappingIter<'a, T, C>,
+pub struct TimeReversed<C> {
+    inner: C,
 }
 
-impl<'a, T, C> Iterator for MappingIterToFlattenVec<'a, T, C>
+impl<C> DelayCoordinates for TimeReversed<C>
 where
-    T: std::iter::IntoIterator + Clone,
     C: DelayCoordinates,
 {
-    type Item = Vec<<T as std::iter::IntoIterator>::Item>;
+    #[inline]
+    fn delay(&self) -> usize { self.inner.delay() }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        self.iter.next().map(|view| view.to_flatten_vec())
+    #[inline]
+    fn dimension(&self) -> usize { self.inner.dimension() }
+
+    #[inline]
+    fn window_size(&self) -> usize { self.inner.window_size() }
+
+    #[inline]
+    fn map_coord(&self, index: usize) -> Option<usize> {
+        self.inner.map_coord(index).map(|pos| self.window_size()-1-pos)
     }
 }
 
-/// View of a slice mapped in delay-coordinates
+/// Delay-coordinates with a subset of the coordinates of other delay-coordinates dropped
 ///
-/// This struct provides an access for the underlying slice with indices in delay-coordinates.
+/// The dropped coordinates are omitted from the embedded vectors, so the dimension is reduced
+/// and the other coordinates keep their order. The window size is that of the inner
+/// delay-coordinates, so the windows are aligned with the full embedding. `embed_zeroed` keeps
+/// the dimension instead and sets the dropped coordinates to zero.
 #[derive(Debug, Clone)]
-pub struct DelayMappedView<'a, T, C> {
-    coord: &'a C,
-    slice: &'a [T],
+pub struct DropoutCoordinates<C> {
+    inner: C,
+    kept: Vec<usize>,
 }
 
-impl<'a, T, C> DelayMappedView<'a, T, C>
+impl<C> DropoutCoordinates<C>
 where
     C: DelayCoordinates,
 {
-    #[inline]
-    pub fn get(&self, index: usize) -> Option<&'a T> {
-        self.coord.map_coord(index).and_then(|pos| self.slice.get(pos))
+    /// Drops the coordinates of `inner` at the indices in `dropped`
+    ///
+    /// Indices not less than the dimension are ignored.
+    pub fn new(inner: C, dropped: &[usize]) -> Self {
+        let kept = (0..inner.dimension()).filter(|index| !dropped.contains(index)).collect();
+        DropoutCoordinates {
+            inner,
+            kept,
+        }
     }
 
-    #[inline]
-    pub fn iter(&'a self) -> DelayMappedViewIter<'a, T, C> {
-        DelayMappedViewIter {
-            view: self,
-            index: 0,
-        }
+    /// Embeds `slice` with the inner delay-coordinates and the dropped coordinates set to zero
+    pub fn embed_zeroed(&self, slice: &[f64]) -> Vec<Vec<f64>> {
+        self.inner.mapping_iter(slice)
+            .map(|view| {
+                (0..self.inner.dimension())
+                    .map(|index| if self.kept.contains(&index) { view[index] } else { 0.0 })
+                    .collect()
+            })
+            .collect()
     }
 }
 
-impl<'a, T, C> DelayMappedView<'a, T, C>
+impl<C> DelayCoordinates for DropoutCoordinates<C>
 where
-    T: Clone,
     C: DelayCoordinates,
 {
-    pub fn to_vec(&self) -> Vec<T> {
-        (0..self.coord.dimension())
-            .map(|index| self[index].clone())
-            .collect()
+    #[inline]
+    fn delay(&self) -> usize { self.inner.delay() }
+
+    #[inline]
+    fn dimension(&self) -> usize { self.kept.len() }
+
+    #[inline]
+    fn window_size(&self) -> usize { self.inner.window_size() }
+
+    #[inline]
+    fn map_coord(&self, index: usize) -> Option<usize> {
+        self.kept.get(index).and_then(|&inner| self.inner.map_coord(inner))
     }
+
+    #[inline]
+    fn is_causal(&self) -> bool { self.inner.is_causal() }
 }
 
-impl<'a, T, C> DelayMappedView<'a, T, C>
+/// Delay-coordinates smoothed by the median of a neighborhood of each coordinate
+///
+/// The window of the inner delay-coordinates is widened by `radius` samples on both sides, so
+/// the coordinates map to the centers of their neighborhoods of $2r + 1$ samples and the
+/// reference time moves `radius` samples later. `map_value` and `embed_smoothed` read the median
+/// of each neighborhood, which is not moved by a single outlier as the mean is.
+#[derive(Debug, Clone)]
+pub struct MedianSmoothedCoordinates<C> {
+    inner: C,
+    radius: usize,
+}
+
+impl<C> MedianSmoothedCoordinates<C>
 where
-    T: std::iter::IntoIterator + Clone,
     C: DelayCoordinates,
 {
-    pub fn to_flatten_vec(&self) -> Vec<<T as std::iter::IntoIterator>::Item> {
-        (0..self.coord.dimension())
-            .map(|index| self[index].clone())
-            .flatten()
+    /// Smooths each coordinate of `inner` over `radius` samples on both sides
+    pub fn new(inner: C, radius: usize) -> Self {
+        MedianSmoothedCoordinates {
+            inner,
+            radius,
+        }
+    }
+
+    /// Median of the neighborhood of the `index`-th coordinate in `window`
+    ///
+    /// The samples are ordered by `f64::total_cmp`, which puts a NaN above every number, so a
+    /// single NaN in the neighborhood is treated as an outlier and the median is a number as
+    /// long as NaNs are fewer than half of the samples.
+    /// Returns `None` if the index is not less than the dimension.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `window` is shorter than the window size.
+    pub fn map_value(&self, window: &[f64], index: usize) -> Option<f64> {
+        let pos = self.map_coord(index)?;
+        let mut neighborhood = window[pos-self.radius..=pos+self.radius].to_vec();
+        neighborhood.sort_by(|a, b| a.total_cmp(b));
+        Some(neighborhood[self.radius])
+    }
+
+    /// Embeds `slice` with each coordinate replaced by the median of its neighborhood
+    pub fn embed_smoothed(&self, slice: &[f64]) -> Vec<Vec<f64>> {
+        if self.window_size() == 0 {
+            return Vec::new();
+        }
+        slice.windows(self.window_size())
+            .map(|window| (0..self.dimension()).filter_map(|index| self.map_value(window, index)).collect())
             .collect()
     }
 }
 
-impl<'a, T, C> Index<usize> for DelayMappedView<'a, T, C>
+impl<C> DelayCoordinates for MedianSmoothedCoordinates<C>
 where
     C: DelayCoordinates,
 {
-    type Output = T;
+    #[inline]
+    fn delay(&self) -> usize { self.inner.delay() }
 
     #[inline]
-    fn index(&self, index: usize) -> &Self::Output {
-        self.get(index).unwrap()
+    fn dimension(&self) -> usize { self.inner.dimension() }
+
+    #[inline]
+    fn window_size(&self) -> usize { self.inner.window_size() + 2 * self.radius }
+
+    #[inline]
+    fn map_coord(&self, index: usize) -> Option<usize> {
+        self.inner.map_coord(index).map(|pos| pos + self.radius)
     }
+
+    #[inline]
+    fn is_causal(&self) -> bool { self.inner.is_causal() }
 }
 
+/// Log-spaced delay-coordinates
+///
+/// Log-spaced delay-coordinates is the non-uniform delay-coordinates with the lags
+/// $l_i = m k^i$ for $i = 0, 1, \ldots, d-1$, where $m$ is the base delay and $k$ is the factor.
 #[derive(Debug, Clone)]
-pub struct DelayMappedViewIter<'a, T, C> {
-    view: &'a DelayMappedView<'a, T, C>,
-    index: usize ,
+pub struct LogDelayCoordinates {
+    pub base_delay: usize,
+    pub dimension: usize,
+    pub factor: usize,
 }
 
-impl<'a, T, C> Iterator for DelayMappedViewIter<'a, T, C>
-where
-    C: DelayCoordinates,
-{
-    type Item = &'a T;
-
+impl LogDelayCoordinates {
+    /// Lag of the `index`-th coordinate
     #[inline]
-    fn next(&mut self) -> Option<Self::Item> {
-        let item = self.view.get(self.index);
-        self.index += 1;
-        item
+    fn lag(&self, index: usize) -> usize {
+        self.base_delay * self.factor.pow(index as u32)
+    }
+
+    /// Lags of the coordinates
+    pub fn lags(&self) -> Vec<usize> {
+        (0..self.dimension).map(|index| self.lag(index)).collect()
+    }
+
+    /// Converts into the equivalent non-uniform delay-coordinates
+    pub fn to_non_uniform(&self) -> NonUniformDelayCoordinates {
+        NonUniformDelayCoordinates {
+            lags: self.lags(),
+        }
     }
 }
 
-#[cfg(test)]
-mod test {
-    use crate::{DelayCoordinates, ForwardDelayCoordinates};
+impl DelayCoordinates for LogDelayCoordinates {
+    #[inline]
+    fn delay(&self) -> usize { self.base_delay }
 
-    #[test]
-    fn test_forward_coord() {
-        let data = (0..10).collect::<Vec<usize>>();
-        let coord = ForwardDelayCoordinates {
-            delay: 2,
-            dimension: 3,
-        };
-        let mut iter = coord.mapping_iter(&data).map(|p| p.to_vec());
-        assert_eq!(iter.next(), Some(vec![4, 2, 0]));
-        assert_eq!(iter.next(), Some(vec![5, 3, 1]));
-        assert_eq!(iter.next(), Some(vec![6, 4, 2]));
-        assert_eq!(iter.next(), Some(vec![7, 5, 3]));
-        assert_eq!(iter.next(), Some(vec![8, 6, 4]));
-        assert_eq!(iter.next(), Some(vec![9, 7, 5]));
-        assert_eq!(iter.next(), None);
+    #[inline]
+    fn dimension(&self) -> usize { self.dimension }
+
+    #[inline]
+    fn window_size(&self) -> usize {
+        if self.dimension == 0 { 0 } else { self.lag(self.dimension-1)+1 }
+    }
+
+    #[inline]
+    fn map_coord(&self, index: usize) -> Option<usize> {
+        if index < self.dimension {
+            Some(self.lag(self.dimension-1)-self.lag(index))
+        } else {
+            None
+        }
+    }
+}
+
+/// Collects the embedded vectors of a scalar series
+pub(crate) fn embed<C>(coord: &C, slice: &[f64]) -> Vec<Vec<f64>>
+where
+    C: DelayCoordinates,
+{
+    coord.mapping_iter(slice).to_vec().collect()
+}
+
+/// Collects the embedded vectors paired with the sample `horizon` steps after each window
+///
+/// The windows whose target is beyond the end of the series are dropped.
+pub(crate) fn embed_with_targets<C>(coord: &C, slice: &[f64], horizon: usize) -> (Vec<Vec<f64>>, Vec<f64>)
+where
+    C: DelayCoordinates,
+{
+    let ws = coord.window_size();
+    if slice.len() < ws + horizon {
+        return (Vec::new(), Vec::new());
+    }
+    let points = embed(coord, &slice[..slice.len()-horizon]);
+    let targets = (0..points.len()).map(|i| slice[i+ws-1+horizon]).collect();
+    (points, targets)
+}
+
+/// Folds over the views of the windows of `slice` without collecting them
+pub fn fold_windows<'a, T, C, B, F>(coord: &'a C, slice: &'a [T], init: B, mut f: F) -> B
+where
+    C: DelayCoordinates,
+    F: FnMut(B, &DelayMappedView<'a, T, C>) -> B,
+{
+    coord.mapping_iter(slice).fold(init, |acc, view| f(acc, &view))
+}
+
+/// Embeds a series into a training and a test trajectory
+///
+/// The series is split at `floor(len * split_fraction)` and each part is embedded separately,
+/// so the windows straddling the split are dropped and no test sample leaks into the training set.
+///
+/// # Panics
+///
+/// Panics if `split_fraction` is not in `[0, 1]`.
+pub fn embed_train_test<C>(series: &[f64], coord: &C, split_fraction: f64) -> (Vec<Vec<f64>>, Vec<Vec<f64>>)
+where
+    C: DelayCoordinates,
+{
+    assert!((0.0..=1.0).contains(&split_fraction), "split fraction must be in [0, 1]");
+    let split = (series.len() as f64 * split_fraction) as usize;
+    let (train, test) = series.split_at(split);
+    (embed(coord, train), embed(coord, test))
+}
+
+/// Embeds a series into the past and the future of each center time
+///
+/// For a center time $t$, the past vector is the window ending at $t$ mapped by `coord` and
+/// the future vector is the window starting at $t$ mapped by `coord.time_reversed()`, so
+/// both have the sample at $t$ at the same coordinates and extend away from it in opposite
+/// directions. Only the center times with a full window on both sides, from `window_size - 1`
+/// to `len - window_size`, are returned as `(t, past, future)` in increasing order of $t$.
+pub fn embed_bidirectional<C>(series: &[f64], coord: &C) -> Vec<(usize, Vec<f64>, Vec<f64>)>
+where
+    C: DelayCoordinates + Clone,
+{
+    let ws = coord.window_size();
+    if ws == 0 || series.len() < 2 * ws - 1 {
+        return Vec::new();
+    }
+    let reversed = coord.time_reversed();
+    let past = coord.mapping_iter(&series[..series.len()-ws+1]).to_vec();
+    let future = reversed.mapping_iter(&series[ws-1..]).to_vec();
+    past.zip(future)
+        .enumerate()
+        .map(|(k, (past, future))| (k+ws-1, past, future))
+        .collect()
+}
+
+/// Embeds a series under each of the given forward delay-coordinates
+///
+/// The $k$-th element of the result is the trajectory for `params[k]`.
+pub fn embed_sweep(series: &[f64], params: &[ForwardDelayCoordinates]) -> Vec<Vec<Vec<f64>>> {
+    params.iter().map(|coord| embed(coord, series)).collect()
+}
+
+/// Embeds a fixed-size array into forward delay-coordinates of dimension `N`
+///
+/// Both the input length and the dimension are compile-time constants and the embedded vectors
+/// are returned as arrays, so no heap allocation takes place.
+pub fn embed_const<T, const LEN: usize, const N: usize>(data: &[T; LEN], delay: usize) -> impl Iterator<Item = [T; N]> + '_
+where
+    T: Copy,
+{
+    let window_size = N.saturating_sub(1) * delay + 1;
+    (0..(LEN+1).saturating_sub(window_size))
+        .map(move |t| std::array::from_fn(|index| data[t+(N-index-1)*delay]))
+}
+
+/// Embeds an already-embedded trajectory
+///
+/// Each element of `trajectory` is treated as one multivariate sample, so the result is a delay
+/// vector of delay vectors flattened into one vector. For example, embedding a trajectory
+/// obtained with delay $m_1$ and dimension $d_1$ again with delay $m_2$ and dimension $d_2$
+/// yields vectors of length $d_1 d_2$ ordered as the coordinates of the outer embedding.
+pub fn embed_iterated<C>(coord: &C, trajectory: &[Vec<f64>]) -> Vec<Vec<f64>>
+where
+    C: DelayCoordinates,
+{
+    coord.mapping_iter(trajectory).to_flatten_vec().collect()
+}
+
+/// Iterator over the views of the sliding windows, created by `DelayCoordinates::mapping_iter`
+pub type MappingIter<'a, T, C> = WindowedViews<'a, T, C, Sliding>;
+
+/// Alignment of labels with windows
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LabelAlign {
+    /// The label at the first sample of the window
+    Start,
+    /// The label at the last sample of the window
+    End,
+}
+
+impl<'a, T, C> MappingIter<'a, T, C>
+where
+    C: DelayCoordinates,
+{
+    /// Pairs each window with a label from `labels`
+    ///
+    /// `labels` is indexed as the underlying series and `align` selects the sample of the window
+    /// whose label is taken. The iteration stops when the labels run out.
+    pub fn zip_labels<L>(self, labels: &'a [L], align: LabelAlign) -> impl Iterator<Item = (DelayMappedView<'a, T, C>, &'a L)> {
+        let shift = match align {
+            LabelAlign::Start => 0,
+            LabelAlign::End => self.coord.window_size().saturating_sub(1),
+        };
+        self.enumerate()
+            .map_while(move |(k, view)| labels.get(k+shift).map(|label| (view, label)))
+    }
+}
+
+impl<'a, T, C> MappingIter<'a, T, C>
+where
+    T: Clone,
+    C: DelayCoordinates,
+{
+    pub fn to_vec(self) -> MappingIterToVec<'a, T, C> {
+        MappingIterToVec {
+            iter: self,
+        }
+    }
+}
+
+impl<'a, T, C> MappingIter<'a, T, C>
+where
+    T: std::iter::IntoIterator + Clone,
+    C: DelayCoordinates,
+{
+    pub fn to_flatten_vec(self) -> MappingIterToFlattenVec<'a, T, C> {
+        MappingIterToFlattenVec {
+            iter: self,
+        }
+    }
+}
+
+/// Handling of the windows running past the end of the series
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TailMode {
+    /// Drops the windows that do not fit in the series
+    #[default]
+    Drop,
+    /// Completes the missing samples with `T::default()`, i.e. zero for numbers
+    ZeroPad,
+    /// Completes the missing samples with the last sample of the series
+    EdgePad,
+}
+
+impl<'a, T, C> MappingIter<'a, T, C>
+where
+    T: Clone + Default,
+    C: DelayCoordinates,
+{
+    /// Maps each window into a vector, handling the windows past the end by `tail`
+    ///
+    /// With `TailMode::ZeroPad` or `TailMode::EdgePad`, a window starts at every index of the
+    /// series, so the last `window_size - 1` windows have their missing future samples padded.
+    /// With `TailMode::Drop`, this is the same as `to_vec`. As with `to_vec`, the windows already
+    /// taken from the iterator are not repeated.
+    pub fn to_vec_with_tail(self, tail: TailMode) -> TailMappingIter<'a, T, C> {
+        TailMappingIter {
+            coord: self.coord,
+            slice: self.slice,
+            start: self.windower.next,
+            tail,
+        }
+    }
+}
+
+/// Iterator over the padded vectors, created by `MappingIter::to_vec_with_tail`
+#[derive(Debug, Clone)]
+pub struct TailMappingIter<'a, T, C> {
+    coord: &'a C,
+    slice: &'a [T],
+    start: usize,
+    tail: TailMode,
+}
+
+impl<'a, T, C> Iterator for TailMappingIter<'a, T, C>
+where
+    T: Clone + Default,
+    C: DelayCoordinates,
+{
+    type Item = Vec<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let fits = self.start + self.coord.window_size() <= self.slice.len();
+        if self.start >= self.slice.len() || (!fits && self.tail == TailMode::Drop) {
+            return None;
+        }
+        let vector = (0..self.coord.dimension())
+            .filter_map(|index| self.coord.map_coord(index))
+            .map(|pos| match self.slice.get(self.start+pos) {
+                Some(x) => x.clone(),
+                None if self.tail == TailMode::EdgePad => self.slice[self.slice.len()-1].clone(),
+                None => T::default(),
+            })
+            .collect();
+        self.start += 1;
+        Some(vector)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MappingIterToVec<'a, T, C> {
+    iter: MappingIter<'a, T, C>,
+}
+
+impl<'a, T, C> Iterator for MappingIterToVec<'a, T, C>
+where
+    T: Clone,
+    C: DelayCoordinates,
+{
+    type Item = Vec<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().map(|view| view.to_vec())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MappingIterToFlattenVec<'a, T, C> {
+    iter: MappingIter<'a, T, C>,
+}
+
+impl<'a, T, C> Iterator for MappingIterToFlattenVec<'a, T, C>
+where
+    T: std::iter::IntoIterator + Clone,
+    C: DelayCoordinates,
+{
+    type Item = Vec<<T as std::iter::IntoIterator>::Item>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().map(|view| view.to_flatten_vec())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SkipNanMappingIter<'a, C> {
+    iter: MappingIter<'a, f64, C>,
+}
+
+impl<'a, C> Iterator for SkipNanMappingIter<'a, C>
+where
+    C: DelayCoordinates,
+{
+    type Item = DelayMappedView<'a, f64, C>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.by_ref()
+            .find(|view| (0..view.coord.dimension()).all(|index| !view[index].is_nan()))
+    }
+}
+
+/// Source of the windows of a series
+///
+/// A windower chooses where the windows start, and `WindowedViews` maps each of them into a
+/// `DelayMappedView`.
+pub trait Windower {
+    /// Start of the next window in a series of length `len`
+    ///
+    /// Returns `None` when there are no more windows. The iteration stops at the first start
+    /// whose window does not fit in the series.
+    fn next_start(&mut self, len: usize, window_size: usize) -> Option<usize>;
+}
+
+/// Windower of the windows starting at every sample, used by `mapping_iter`
+#[derive(Debug, Clone, Default)]
+pub struct Sliding {
+    next: usize,
+}
+
+impl Sliding {
+    pub fn new() -> Self {
+        Sliding {
+            next: 0,
+        }
+    }
+}
+
+impl Windower for Sliding {
+    fn next_start(&mut self, len: usize, window_size: usize) -> Option<usize> {
+        if self.next + window_size > len {
+            None
+        } else {
+            self.next += 1;
+            Some(self.next - 1)
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct WindowedViews<'a, T, C, W> {
+    coord: &'a C,
+    slice: &'a [T],
+    windower: W,
+}
+
+impl<'a, T, C, W> Iterator for WindowedViews<'a, T, C, W>
+where
+    C: DelayCoordinates,
+    W: Windower,
+{
+    type Item = DelayMappedView<'a, T, C>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let window_size = self.coord.window_size();
+        let start = self.windower.next_start(self.slice.len(), window_size)?;
+        self.slice.get(start..start+window_size)
+            .map(|slice| DelayMappedView {
+                coord: self.coord,
+                slice,
+            })
+    }
+}
+
+/// View of a slice mapped in delay-coordinates
+///
+/// This struct provides an access for the underlying slice with indices in delay-coordinates.
+#[derive(Debug, Clone)]
+pub struct DelayMappedView<'a, T, C> {
+    coord: &'a C,
+    slice: &'a [T],
+}
+
+impl<'a, T, C> DelayMappedView<'a, T, C>
+where
+    C: DelayCoordinates,
+{
+    #[inline]
+    pub fn get(&self, index: usize) -> Option<&'a T> {
+        self.coord.map_coord(index).and_then(|pos| self.slice.get(pos))
+    }
+
+    #[inline]
+    pub fn iter(&'a self) -> DelayMappedViewIter<'a, T, C> {
+        DelayMappedViewIter {
+            view: self,
+            index: 0,
+            end: self.coord.dimension(),
+        }
+    }
+
+    /// Iterates over the coordinates from the last one to the first one
+    #[inline]
+    pub fn rev_iter(&'a self) -> impl Iterator<Item = &'a T> {
+        self.iter().rev()
+    }
+
+    /// Samples of the whole window, including those not mapped to any coordinate
+    #[inline]
+    pub fn window_slice(&self) -> &'a [T] {
+        self.slice
+    }
+}
+
+impl<'a, T, C> DelayMappedView<'a, T, C>
+where
+    T: Clone,
+    C: DelayCoordinates,
+{
+    pub fn to_vec(&self) -> Vec<T> {
+        (0..self.coord.dimension())
+            .map(|index| self[index].clone())
+            .collect()
+    }
+}
+
+impl<'a, T, C> DelayMappedView<'a, T, C>
+where
+    T: std::iter::IntoIterator + Clone,
+    C: DelayCoordinates,
+{
+    pub fn to_flatten_vec(&self) -> Vec<<T as std::iter::IntoIterator>::Item> {
+        (0..self.coord.dimension())
+            .flat_map(|index| self[index].clone())
+            .collect()
+    }
+}
+
+impl<'a, T, C> DelayMappedView<'a, T, C>
+where
+    C: DelayCoordinates,
+{
+    /// Iterates over the elements of the multivariate samples in the order of `to_flatten_vec`
+    ///
+    /// The elements are borrowed from the underlying slice instead of being cloned.
+    pub fn iter_flatten<U>(&self) -> impl Iterator<Item = &'a U> + 'a
+    where
+        T: AsRef<[U]>,
+        U: 'a,
+    {
+        let coord = self.coord;
+        let slice = self.slice;
+        (0..coord.dimension())
+            .flat_map(move |index| slice[coord.map_coord(index).unwrap()].as_ref().iter())
+    }
+}
+
+impl<'a, C> DelayMappedView<'a, f64, C>
+where
+    C: DelayCoordinates,
+{
+    /// Embedded vector multiplied by the taper `window` across the coordinates
+    pub fn to_windowed_vec(&self, window: WindowFn) -> Vec<f64> {
+        let weights = window.weights(self.coord.dimension());
+        (0..self.coord.dimension())
+            .map(|index| self[index] * weights[index])
+            .collect()
+    }
+}
+
+/// Taper applied across the coordinates of an embedded vector
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WindowFn {
+    /// $w(i) = 0.5 - 0.5 \cos(2 \pi i / (n-1))$
+    Hann,
+    /// $w(i) = 0.54 - 0.46 \cos(2 \pi i / (n-1))$
+    Hamming,
+    /// $w(i) = 0.42 - 0.5 \cos(2 \pi i / (n-1)) + 0.08 \cos(4 \pi i / (n-1))$
+    Blackman,
+}
+
+impl WindowFn {
+    /// Symmetric weights of a window of length `n`
+    ///
+    /// A window of length 1 has the weight 1.
+    pub fn weights(&self, n: usize) -> Vec<f64> {
+        if n == 1 {
+            return vec![1.0];
+        }
+        (0..n)
+            .map(|i| {
+                let x = 2.0 * std::f64::consts::PI * i as f64 / (n - 1) as f64;
+                match self {
+                    WindowFn::Hann => 0.5 - 0.5 * x.cos(),
+                    WindowFn::Hamming => 0.54 - 0.46 * x.cos(),
+                    WindowFn::Blackman => 0.42 - 0.5 * x.cos() + 0.08 * (2.0 * x).cos(),
+                }
+            })
+            .collect()
+    }
+}
+
+impl<'a, T, C> Index<usize> for DelayMappedView<'a, T, C>
+where
+    C: DelayCoordinates,
+{
+    type Output = T;
+
+    #[inline]
+    fn index(&self, index: usize) -> &Self::Output {
+        self.get(index).unwrap()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DelayMappedViewIter<'a, T, C> {
+    view: &'a DelayMappedView<'a, T, C>,
+    index: usize,
+    end: usize,
+}
+
+impl<'a, T, C> Iterator for DelayMappedViewIter<'a, T, C>
+where
+    C: DelayCoordinates,
+{
+    type Item = &'a T;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index < self.end {
+            self.index += 1;
+            self.view.get(self.index-1)
+        } else {
+            None
+        }
+    }
+}
+
+impl<'a, T, C> DoubleEndedIterator for DelayMappedViewIter<'a, T, C>
+where
+    C: DelayCoordinates,
+{
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.index < self.end {
+            self.end -= 1;
+            self.view.get(self.end)
+        } else {
+            None
+        }
+    }
+}
+
+/// View of a window in delay-coordinates owning its data
+///
+/// Unlike `DelayMappedView`, this struct shares the underlying series through an `Arc`,
+/// so it can outlive the series it was created from.
+#[derive(Debug, Clone)]
+pub struct OwnedDelayView<T, C> {
+    coord: C,
+    data: Arc<[T]>,
+    offset: usize,
+}
+
+impl<T, C> OwnedDelayView<T, C>
+where
+    C: DelayCoordinates,
+{
+    /// Creates a view of the window of `data` starting at `offset`
+    ///
+    /// Returns `None` if the window does not fit in `data`.
+    pub fn new(data: Arc<[T]>, coord: C, offset: usize) -> Option<Self> {
+        if offset + coord.window_size() <= data.len() {
+            Some(OwnedDelayView {
+                coord,
+                data,
+                offset,
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Index of the first sample of the window in the underlying series
+    #[inline]
+    pub fn offset(&self) -> usize { self.offset }
+
+    #[inline]
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.coord.map_coord(index).map(|pos| &self.data[self.offset+pos])
+    }
+
+    #[inline]
+    pub fn iter(&self) -> OwnedDelayViewIter<'_, T, C> {
+        OwnedDelayViewIter {
+            view: self,
+            index: 0,
+        }
+    }
+}
+
+impl<T, C> OwnedDelayView<T, C>
+where
+    T: Clone,
+    C: DelayCoordinates,
+{
+    pub fn to_vec(&self) -> Vec<T> {
+        self.iter().cloned().collect()
+    }
+}
+
+impl<T, C> Index<usize> for OwnedDelayView<T, C>
+where
+    C: DelayCoordinates,
+{
+    type Output = T;
+
+    #[inline]
+    fn index(&self, index: usize) -> &Self::Output {
+        self.get(index).unwrap()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct OwnedDelayViewIter<'a, T, C> {
+    view: &'a OwnedDelayView<T, C>,
+    index: usize,
+}
+
+impl<'a, T, C> Iterator for OwnedDelayViewIter<'a, T, C>
+where
+    C: DelayCoordinates,
+{
+    type Item = &'a T;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = self.view.get(self.index);
+        self.index += 1;
+        item
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{DelayCoordinates, ForwardDelayCoordinates, NonUniformDelayCoordinates, OwnedDelayView};
+    use crate::{CoordInfo, CoordKind, LabelAlign, LogDelayCoordinates, TailMode};
+    use crate::{DropoutCoordinates, Error, MedianSmoothedCoordinates, MixedDelayCoordinates, RandomDelayCoordinates, Sliding, WindowFn, Windower, require_causal};
+    use crate::{embed_bidirectional, embed_const, embed_iterated, embed_sweep, embed_train_test, fold_windows};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_forward_coord() {
+        let data = (0..10).collect::<Vec<usize>>();
+        let coord = ForwardDelayCoordinates {
+            delay: 2,
+            dimension: 3,
+        };
+        let mut iter = coord.mapping_iter(&data).map(|p| p.to_vec());
+        assert_eq!(iter.next(), Some(vec![4, 2, 0]));
+        assert_eq!(iter.next(), Some(vec![5, 3, 1]));
+        assert_eq!(iter.next(), Some(vec![6, 4, 2]));
+        assert_eq!(iter.next(), Some(vec![7, 5, 3]));
+        assert_eq!(iter.next(), Some(vec![8, 6, 4]));
+        assert_eq!(iter.next(), Some(vec![9, 7, 5]));
+        assert_eq!(iter.next(), None);
     }
 
     #[test]
@@ -297,4 +1313,525 @@ mod test {
         assert_eq!(iter.next(), Some(vec![9, 9, 4, 4]));
         assert_eq!(iter.next(), None);
     }
+
+    #[test]
+    fn test_forward_coord_to_transposed_flat() {
+        let data = (0..10).collect::<Vec<usize>>();
+        let coord = ForwardDelayCoordinates {
+            delay: 2,
+            dimension: 3,
+        };
+        let (flat, rows, cols) = coord.to_transposed_flat(&data);
+        assert_eq!((rows, cols), (6, 3));
+        for (row, v) in coord.mapping_iter(&data).to_vec().enumerate() {
+            for col in 0..cols {
+                assert_eq!(flat[col*rows+row], v[col]);
+            }
+        }
+        assert_eq!(coord.to_transposed_flat(&data[..4]), (vec![], 0, 3));
+    }
+
+    #[test]
+    fn test_embed_iterated() {
+        let data = (0..10).map(|n| n as f64).collect::<Vec<f64>>();
+        let inner = ForwardDelayCoordinates {
+            delay: 1,
+            dimension: 2,
+        };
+        let outer = ForwardDelayCoordinates {
+            delay: 2,
+            dimension: 2,
+        };
+        let trajectory = inner.mapping_iter(&data).to_vec().collect::<Vec<_>>();
+        let nested = embed_iterated(&outer, &trajectory);
+        assert_eq!(nested.len(), 7);
+        assert_eq!(nested[0], vec![3.0, 2.0, 1.0, 0.0]);
+        assert_eq!(nested[6], vec![9.0, 8.0, 7.0, 6.0]);
+    }
+
+    #[test]
+    fn test_non_uniform_coord() {
+        let data = (0..10).collect::<Vec<usize>>();
+        let coord = NonUniformDelayCoordinates {
+            lags: vec![0, 1, 4],
+        };
+        assert_eq!(coord.delay(), 1);
+        assert_eq!(coord.window_size(), 5);
+        let mut iter = coord.mapping_iter(&data).to_vec();
+        assert_eq!(iter.next(), Some(vec![4, 3, 0]));
+        assert_eq!(iter.last(), Some(vec![9, 8, 5]));
+
+        let forward = ForwardDelayCoordinates {
+            delay: 2,
+            dimension: 3,
+        };
+        let uniform = NonUniformDelayCoordinates {
+            lags: vec![0, 2, 4],
+        };
+        assert!(forward.mapping_iter(&data).to_vec().eq(uniform.mapping_iter(&data).to_vec()));
+    }
+
+    #[test]
+    fn test_owned_view() {
+        let view = {
+            let data = (0..10).collect::<Vec<usize>>();
+            let coord = ForwardDelayCoordinates {
+                delay: 2,
+                dimension: 3,
+            };
+            OwnedDelayView::new(Arc::from(data), coord, 3).unwrap()
+        };
+        assert_eq!(view.offset(), 3);
+        assert_eq!(view[0], 7);
+        assert_eq!(view.get(3), None);
+        assert_eq!(view.iter().collect::<Vec<_>>(), vec![&7, &5, &3]);
+        assert_eq!(view.to_vec(), vec![7, 5, 3]);
+    }
+
+    #[test]
+    fn test_log_coord() {
+        let data = (0..20).collect::<Vec<usize>>();
+        let coord = LogDelayCoordinates {
+            base_delay: 1,
+            dimension: 4,
+            factor: 2,
+        };
+        assert_eq!(coord.lags(), vec![1, 2, 4, 8]);
+        assert_eq!(coord.window_size(), 9);
+        let mut iter = coord.mapping_iter(&data).to_vec();
+        assert_eq!(iter.next(), Some(vec![7, 6, 4, 0]));
+        let non_uniform = coord.to_non_uniform();
+        assert!(coord.mapping_iter(&data).to_vec().eq(non_uniform.mapping_iter(&data).to_vec()));
+    }
+
+    #[test]
+    fn test_forward_coord_describe() {
+        let coord = ForwardDelayCoordinates {
+            delay: 2,
+            dimension: 3,
+        };
+        let info = |lag| CoordInfo {
+            channel: None,
+            lag,
+            kind: CoordKind::Delay,
+        };
+        assert_eq!(coord.describe(), vec![info(0), info(2), info(4)]);
+    }
+
+    #[test]
+    fn test_mixed_coord_describe() {
+        let coord = MixedDelayCoordinates {
+            lags: vec![-1, 0, 2],
+        };
+        let info = |lag, kind| CoordInfo {
+            channel: None,
+            lag,
+            kind,
+        };
+        let expected = vec![info(1, CoordKind::Lead), info(0, CoordKind::Delay), info(2, CoordKind::Delay)];
+        assert_eq!(coord.describe(), expected);
+    }
+
+    #[test]
+    fn test_mapping_iter_skip_nan() {
+        let mut data = (0..10).map(|n| n as f64).collect::<Vec<f64>>();
+        data[5] = f64::NAN;
+        let coord = ForwardDelayCoordinates {
+            delay: 2,
+            dimension: 2,
+        };
+        let rows = coord.mapping_iter_skip_nan(&data).map(|view| view.to_vec()).collect::<Vec<_>>();
+        assert_eq!(rows, vec![
+            vec![2.0, 0.0],
+            vec![3.0, 1.0],
+            vec![4.0, 2.0],
+            vec![6.0, 4.0],
+            vec![8.0, 6.0],
+            vec![9.0, 7.0],
+        ]);
+    }
+
+    #[test]
+    fn test_zip_labels() {
+        let data = (0..10).collect::<Vec<usize>>();
+        let labels = (0..10).map(|n| n * 10).collect::<Vec<usize>>();
+        let coord = ForwardDelayCoordinates {
+            delay: 2,
+            dimension: 3,
+        };
+        let start = coord.mapping_iter(&data)
+            .zip_labels(&labels, LabelAlign::Start)
+            .map(|(view, &label)| (view.to_vec(), label))
+            .collect::<Vec<_>>();
+        assert_eq!(start.len(), 6);
+        assert_eq!(start[0], (vec![4, 2, 0], 0));
+        assert_eq!(start[5], (vec![9, 7, 5], 50));
+        let end = coord.mapping_iter(&data)
+            .zip_labels(&labels[..8], LabelAlign::End)
+            .map(|(view, &label)| (view.to_vec(), label))
+            .collect::<Vec<_>>();
+        assert_eq!(end, vec![(vec![4, 2, 0], 40), (vec![5, 3, 1], 50), (vec![6, 4, 2], 60), (vec![7, 5, 3], 70)]);
+    }
+
+    #[test]
+    fn test_embed_const() {
+        let data = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9];
+        let mut iter = embed_const::<_, 10, 3>(&data, 2);
+        assert_eq!(iter.next(), Some([4, 2, 0]));
+        assert_eq!(iter.last(), Some([9, 7, 5]));
+        assert_eq!(embed_const::<_, 10, 3>(&data, 2).count(), 6);
+        assert_eq!(embed_const::<_, 3, 3>(&[0, 1, 2], 2).count(), 0);
+    }
+
+    #[test]
+    fn test_time_reversed() {
+        let data = (0..10).map(|n| n * n).collect::<Vec<usize>>();
+        let coord = ForwardDelayCoordinates {
+            delay: 2,
+            dimension: 3,
+        };
+        let reversed_coord = coord.time_reversed();
+        let mut reversed_data = data.clone();
+        reversed_data.reverse();
+        let mut forward = coord.mapping_iter(&reversed_data).to_vec().collect::<Vec<_>>();
+        forward.reverse();
+        let reversed = reversed_coord.mapping_iter(&data).to_vec().collect::<Vec<_>>();
+        assert_eq!(forward, reversed);
+        assert_eq!(reversed[0], vec![0, 4, 16]);
+        assert_ne!(reversed, coord.mapping_iter(&data).to_vec().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_embed_sweep() {
+        let data = (0..10).map(|n| n as f64).collect::<Vec<f64>>();
+        let params = [
+            ForwardDelayCoordinates { delay: 1, dimension: 2 },
+            ForwardDelayCoordinates { delay: 3, dimension: 3 },
+        ];
+        let sweep = embed_sweep(&data, &params);
+        assert_eq!(sweep.len(), 2);
+        assert_eq!(sweep[0].len(), 9);
+        assert_eq!(sweep[0][0], vec![1.0, 0.0]);
+        assert_eq!(sweep[1].len(), 4);
+        assert_eq!(sweep[1][0], vec![6.0, 3.0, 0.0]);
+    }
+
+    #[test]
+    fn test_fold_windows() {
+        let data = (0..10).collect::<Vec<usize>>();
+        let coord = ForwardDelayCoordinates {
+            delay: 2,
+            dimension: 3,
+        };
+        let sum = fold_windows(&coord, &data, 0, |acc, view| acc + view[0] + view[1] + view[2]);
+        let expected = coord.mapping_iter(&data).to_vec().flatten().sum::<usize>();
+        assert_eq!(sum, expected);
+        assert_eq!(sum, 81);
+    }
+
+    #[test]
+    fn test_coord_offsets() {
+        let coord = ForwardDelayCoordinates {
+            delay: 2,
+            dimension: 3,
+        };
+        assert_eq!(coord.map_coords(), vec![4, 2, 0]);
+        assert_eq!(coord.coord_offsets::<3>().to_vec(), coord.map_coords());
+        let coord = NonUniformDelayCoordinates {
+            lags: vec![0, 1, 5, 6],
+        };
+        assert_eq!(coord.coord_offsets::<4>().to_vec(), coord.map_coords());
+    }
+
+    #[test]
+    fn test_embed_train_test() {
+        let series = (0..50).map(|t| t as f64).collect::<Vec<f64>>();
+        let coord = ForwardDelayCoordinates {
+            delay: 2,
+            dimension: 3,
+        };
+        let (train, test) = embed_train_test(&series, &coord, 0.6);
+        assert_eq!(train.len(), 26);
+        assert_eq!(test.len(), 16);
+        assert!(train.iter().flatten().all(|&t| t < 30.0));
+        assert!(test.iter().flatten().all(|&t| t >= 30.0));
+        assert_eq!(train[0], vec![4.0, 2.0, 0.0]);
+        assert_eq!(test[0], vec![34.0, 32.0, 30.0]);
+    }
+
+    #[test]
+    fn test_mixed_causality() {
+        let centered = MixedDelayCoordinates {
+            lags: vec![-2, 0, 2],
+        };
+        assert!(!centered.is_causal());
+        assert_eq!(require_causal(&centered), Err(Error::NonCausal));
+        assert_eq!(centered.window_size(), 5);
+        assert_eq!(centered.delay(), 2);
+        let data = (0..6).collect::<Vec<i32>>();
+        let vecs = centered.mapping_iter(&data).to_vec().collect::<Vec<_>>();
+        assert_eq!(vecs, vec![vec![4, 2, 0], vec![5, 3, 1]]);
+
+        let past = MixedDelayCoordinates {
+            lags: vec![0, 1, 3],
+        };
+        assert!(past.is_causal());
+        assert_eq!(require_causal(&past), Ok(()));
+        let forward = ForwardDelayCoordinates {
+            delay: 2,
+            dimension: 3,
+        };
+        assert!(forward.is_causal());
+    }
+
+    #[test]
+    fn test_len_for_windows() {
+        let coord = ForwardDelayCoordinates {
+            delay: 3,
+            dimension: 4,
+        };
+        assert_eq!(coord.len_for_windows(0), 0);
+        assert_eq!(coord.len_for_windows(1), 10);
+        assert_eq!(coord.len_for_windows(5), 14);
+        let data = vec![0.0; coord.len_for_windows(5)];
+        assert_eq!(coord.mapping_iter(&data).count(), 5);
+        let coord = NonUniformDelayCoordinates {
+            lags: vec![0, 2, 7],
+        };
+        assert_eq!(coord.len_for_windows(3), 10);
+    }
+
+    #[test]
+    fn test_iter_flatten() {
+        let data = (0..6).map(|t| vec![t as f64, -(t as f64)]).collect::<Vec<Vec<f64>>>();
+        let coord = ForwardDelayCoordinates {
+            delay: 2,
+            dimension: 2,
+        };
+        for view in coord.mapping_iter(&data) {
+            let borrowed = view.iter_flatten().cloned().collect::<Vec<f64>>();
+            assert_eq!(borrowed, view.to_flatten_vec());
+        }
+        let view = coord.mapping_iter(&data).next().unwrap();
+        let first = view.iter_flatten().next().unwrap();
+        assert!(std::ptr::eq(first, &data[2][0]));
+    }
+
+    #[test]
+    fn test_window_slice() {
+        let data = (0..8).collect::<Vec<i32>>();
+        let coord = ForwardDelayCoordinates {
+            delay: 2,
+            dimension: 3,
+        };
+        let view = coord.mapping_iter(&data).nth(2).unwrap();
+        assert_eq!(view.window_slice(), &[2, 3, 4, 5, 6]);
+        assert_eq!(view.to_vec(), vec![6, 4, 2]);
+    }
+
+    #[test]
+    fn test_rev_iter() {
+        let data = (0..10).collect::<Vec<i32>>();
+        let coord = ForwardDelayCoordinates {
+            delay: 3,
+            dimension: 3,
+        };
+        for view in coord.mapping_iter(&data) {
+            let rev = view.rev_iter().collect::<Vec<_>>();
+            assert_eq!(rev, view.iter().rev().collect::<Vec<_>>());
+            assert_eq!(rev.len(), 3);
+        }
+        let view = coord.mapping_iter(&data).next().unwrap();
+        assert_eq!(view.rev_iter().cloned().collect::<Vec<_>>(), vec![0, 3, 6]);
+        let mut iter = view.iter();
+        assert_eq!(iter.next(), Some(&6));
+        assert_eq!(iter.next_back(), Some(&0));
+        assert_eq!(iter.next(), Some(&3));
+        assert_eq!(iter.next_back(), None);
+    }
+
+    #[test]
+    fn test_to_windowed_vec() {
+        let data = vec![1.0; 20];
+        let coord = ForwardDelayCoordinates {
+            delay: 2,
+            dimension: 5,
+        };
+        let view = coord.mapping_iter(&data).next().unwrap();
+        let hann = view.to_windowed_vec(WindowFn::Hann);
+        assert!(hann[0].abs() < 1e-12 && hann[4].abs() < 1e-12);
+        assert!((hann[2] - 1.0).abs() < 1e-12);
+        assert!((hann[1] - 0.5).abs() < 1e-12);
+        let hamming = view.to_windowed_vec(WindowFn::Hamming);
+        assert!((hamming[0] - 0.08).abs() < 1e-12);
+        let blackman = view.to_windowed_vec(WindowFn::Blackman);
+        assert!(blackman[0].abs() < 1e-12 && (blackman[2] - 1.0).abs() < 1e-12);
+        assert_eq!(WindowFn::Hann.weights(1), vec![1.0]);
+    }
+
+    #[test]
+    fn test_timed_views_rate() {
+        let data = (0..10).collect::<Vec<i32>>();
+        let coord = ForwardDelayCoordinates {
+            delay: 2,
+            dimension: 2,
+        };
+        let timed = coord.timed_views_rate(&data, 4.0, 1.0).collect::<Vec<_>>();
+        assert_eq!(timed.len(), 8);
+        for (k, (t, view)) in timed.iter().enumerate() {
+            assert!((t - (1.0 + 0.25 * k as f64)).abs() < 1e-12);
+            assert_eq!(view.to_vec(), vec![k as i32 + 2, k as i32]);
+        }
+    }
+
+    #[test]
+    fn test_windowed_views() {
+        // Windows starting at the samples where the series crosses a threshold upward
+        struct Triggered<'a> {
+            series: &'a [f64],
+            next: usize,
+        }
+
+        impl<'a> Windower for Triggered<'a> {
+            fn next_start(&mut self, len: usize, window_size: usize) -> Option<usize> {
+                let start = (self.next.max(1)..len).find(|&t| self.series[t-1] < 0.5 && self.series[t] >= 0.5)?;
+                self.next = start + 1;
+                if start + window_size <= len { Some(start) } else { None }
+            }
+        }
+
+        let data = (0..10).map(|t| t as f64).collect::<Vec<f64>>();
+        let coord = ForwardDelayCoordinates {
+            delay: 2,
+            dimension: 3,
+        };
+        let sliding = coord.windowed_views(&data, Sliding::new()).map(|v| v.to_vec()).collect::<Vec<_>>();
+        let mapped = coord.mapping_iter(&data).map(|v| v.to_vec()).collect::<Vec<_>>();
+        assert_eq!(sliding, mapped);
+
+        let series = vec![0.0, 1.0, 0.0, 0.0, 1.0, 1.0, 0.0, 1.0, 0.0];
+        let coord = ForwardDelayCoordinates {
+            delay: 1,
+            dimension: 2,
+        };
+        let triggered = Triggered {
+            series: &series,
+            next: 0,
+        };
+        let views = coord.windowed_views(&series, triggered).map(|v| v.to_vec()).collect::<Vec<_>>();
+        assert_eq!(views, vec![vec![0.0, 1.0], vec![1.0, 1.0], vec![0.0, 1.0]]);
+    }
+
+    #[test]
+    fn test_random_coord() {
+        let coord = RandomDelayCoordinates::new(4, 20, 7);
+        assert_eq!(coord, RandomDelayCoordinates::new(4, 20, 7));
+        assert_ne!(coord.lags(), RandomDelayCoordinates::new(4, 20, 8).lags());
+        let lags = coord.lags();
+        assert_eq!(lags.len(), 4);
+        assert!(lags.windows(2).all(|w| w[0] < w[1]) && lags[3] <= 20);
+
+        let data = (0..30).collect::<Vec<usize>>();
+        let first = coord.mapping_iter(&data).next().unwrap().to_vec();
+        let last = coord.window_size() - 1;
+        assert_eq!(first, lags.iter().map(|lag| last - lag).collect::<Vec<usize>>());
+        assert_eq!(RandomDelayCoordinates::new(3, 2, 1).lags(), &[0, 1, 2]);
+    }
+
+    #[test]
+    fn test_embed_bidirectional() {
+        let data = (0..10).map(|t| t as f64).collect::<Vec<f64>>();
+        let coord = ForwardDelayCoordinates {
+            delay: 2,
+            dimension: 3,
+        };
+        let points = embed_bidirectional(&data, &coord);
+        assert_eq!(points.len(), 2);
+        assert_eq!(points[0], (4, vec![4.0, 2.0, 0.0], vec![4.0, 6.0, 8.0]));
+        assert_eq!(points[1], (5, vec![5.0, 3.0, 1.0], vec![5.0, 7.0, 9.0]));
+        assert!(embed_bidirectional(&data[..8], &coord).is_empty());
+    }
+
+    #[test]
+    fn test_hankel_matrix() {
+        let data = (0..8).collect::<Vec<i32>>();
+        let coord = ForwardDelayCoordinates {
+            delay: 2,
+            dimension: 2,
+        };
+        let hankel = coord.hankel_matrix(&data);
+        assert_eq!(hankel.len(), 6);
+        assert_eq!(hankel[0], vec![0, 1, 2]);
+        assert_eq!(hankel[5], vec![5, 6, 7]);
+        for rows in hankel.windows(2) {
+            assert_eq!(rows[1][..2], rows[0][1..]);
+        }
+        assert!(coord.hankel_matrix(&data[..2]).is_empty());
+    }
+
+    #[test]
+    fn test_dropout_coord() {
+        let data = (0..10).map(|t| t as f64).collect::<Vec<f64>>();
+        let coord = ForwardDelayCoordinates {
+            delay: 2,
+            dimension: 4,
+        };
+        let dropout = DropoutCoordinates::new(coord.clone(), &[1, 3, 7]);
+        assert_eq!(dropout.dimension(), 2);
+        assert_eq!(dropout.window_size(), 7);
+        let omitted = dropout.mapping_iter(&data).map(|v| v.to_vec()).collect::<Vec<_>>();
+        assert_eq!(omitted, vec![vec![6.0, 2.0], vec![7.0, 3.0], vec![8.0, 4.0], vec![9.0, 5.0]]);
+        let zeroed = dropout.embed_zeroed(&data);
+        assert_eq!(zeroed[0], vec![6.0, 0.0, 2.0, 0.0]);
+        assert_eq!(zeroed.len(), coord.mapping_iter(&data).count());
+    }
+
+    #[test]
+    fn test_median_smoothed_coord() {
+        let mut data = (0..12).map(|t| t as f64).collect::<Vec<f64>>();
+        data[5] = 100.0;
+        let coord = ForwardDelayCoordinates {
+            delay: 3,
+            dimension: 2,
+        };
+        let smoothed = MedianSmoothedCoordinates::new(coord, 1);
+        assert_eq!(smoothed.window_size(), 6);
+        assert_eq!(smoothed.map_coords(), vec![4, 1]);
+        assert_eq!(smoothed.map_value(&data[2..8], 1), Some(3.0));
+        assert_eq!(smoothed.map_value(&data[2..8], 2), None);
+        let points = smoothed.embed_smoothed(&data);
+        assert_eq!(points.len(), 7);
+        assert_eq!(points[0], vec![4.0, 1.0]);
+        assert_eq!(points[1], vec![6.0, 2.0]);
+        assert_eq!(points[4], vec![8.0, 6.0]);
+        assert!(points.iter().flatten().all(|&x| x < 12.0));
+        data[5] = f64::NAN;
+        assert_eq!(smoothed.map_value(&data[2..8], 0), Some(7.0));
+        assert!(smoothed.embed_smoothed(&data).iter().flatten().all(|x| !x.is_nan()));
+    }
+
+    #[test]
+    fn test_tail_mode() {
+        let data = (1..=6).collect::<Vec<i32>>();
+        let coord = ForwardDelayCoordinates {
+            delay: 2,
+            dimension: 2,
+        };
+        let dropped = coord.mapping_iter(&data).to_vec_with_tail(TailMode::Drop).collect::<Vec<_>>();
+        assert_eq!(dropped, coord.mapping_iter(&data).to_vec().collect::<Vec<_>>());
+        assert_eq!(dropped.len(), 4);
+        let zero = coord.mapping_iter(&data).to_vec_with_tail(TailMode::ZeroPad).collect::<Vec<_>>();
+        assert_eq!(zero.len(), 6);
+        assert_eq!(zero[..4], dropped[..]);
+        assert_eq!(zero[4..], [vec![0, 5], vec![0, 6]]);
+        let edge = coord.mapping_iter(&data).to_vec_with_tail(TailMode::EdgePad).collect::<Vec<_>>();
+        assert_eq!(edge[4..], [vec![6, 5], vec![6, 6]]);
+        assert_eq!(TailMode::default(), TailMode::Drop);
+        let mut iter = coord.mapping_iter(&data);
+        iter.next();
+        assert_eq!(iter.to_vec_with_tail(TailMode::Drop).collect::<Vec<_>>(), dropped[1..]);
+        let mut iter = coord.mapping_iter(&data);
+        iter.nth(2);
+        assert_eq!(iter.to_vec_with_tail(TailMode::ZeroPad).collect::<Vec<_>>(), zero[3..]);
+    }
 }
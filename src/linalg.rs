@@ -0,0 +1,261 @@
+//! Small dense linear algebra for the estimators
+
+/// Solves the linear system $A x = b$ by Gaussian elimination with partial pivoting
+///
+/// `a` is a square matrix given by rows. Returns `None` if the matrix is singular.
+pub(crate) fn solve(mut a: Vec<Vec<f64>>, mut b: Vec<f64>) -> Option<Vec<f64>> {
+    let n = b.len();
+    for col in 0..n {
+        let pivot = (col..n).max_by(|&i, &j| a[i][col].abs().total_cmp(&a[j][col].abs()))?;
+        if a[pivot][col].abs() < 1e-12 {
+            return None;
+        }
+        a.swap(col, pivot);
+        b.swap(col, pivot);
+        for row in col+1..n {
+            let factor = a[row][col] / a[col][col];
+            let (upper, lower) = a.split_at_mut(row);
+            for (x, y) in lower[0][col..].iter_mut().zip(upper[col][col..].iter()) {
+                *x -= factor * y;
+            }
+            b[row] -= factor * b[col];
+        }
+    }
+    let mut x = vec![0.0; n];
+    for row in (0..n).rev() {
+        let sum = (row+1..n).map(|j| a[row][j] * x[j]).sum::<f64>();
+        x[row] = (b[row] - sum) / a[row][row];
+    }
+    Some(x)
+}
+
+/// Least-squares solution of $X \beta \approx y$ through the normal equations
+///
+/// Each element of `rows` is a row of $X$. Returns `None` if $X^T X$ is singular.
+pub(crate) fn least_squares(rows: &[Vec<f64>], y: &[f64]) -> Option<Vec<f64>> {
+    let p = rows.first().map_or(0, |row| row.len());
+    let mut xtx = vec![vec![0.0; p]; p];
+    let mut xty = vec![0.0; p];
+    for (row, &target) in rows.iter().zip(y.iter()) {
+        for i in 0..p {
+            xty[i] += row[i] * target;
+            for j in 0..p {
+                xtx[i][j] += row[i] * row[j];
+            }
+        }
+    }
+    solve(xtx, xty)
+}
+
+/// Eigendecomposition of a symmetric matrix by the cyclic Jacobi method
+///
+/// `a` is a symmetric matrix given by rows. The eigenvalues are returned in decreasing order
+/// together with the corresponding unit eigenvectors.
+pub(crate) fn symmetric_eigen(mut a: Vec<Vec<f64>>) -> (Vec<f64>, Vec<Vec<f64>>) {
+    let n = a.len();
+    let mut v = (0..n)
+        .map(|i| (0..n).map(|j| if i == j { 1.0 } else { 0.0 }).collect::<Vec<f64>>())
+        .collect::<Vec<_>>();
+    let scale = a.iter().flatten().map(|x| x * x).sum::<f64>();
+    for _ in 0..100 {
+        let off = (0..n).map(|p| (p+1..n).map(|q| a[p][q] * a[p][q]).sum::<f64>()).sum::<f64>();
+        if off <= 1e-30 * scale {
+            break;
+        }
+        for p in 0..n {
+            for q in p+1..n {
+                if a[p][q] == 0.0 {
+                    continue;
+                }
+                let theta = (a[q][q] - a[p][p]) / (2.0 * a[p][q]);
+                let t = theta.signum() / (theta.abs() + (theta * theta + 1.0).sqrt());
+                let c = 1.0 / (t * t + 1.0).sqrt();
+                let s = t * c;
+                for row in a.iter_mut().chain(v.iter_mut()) {
+                    let (x, y) = (row[p], row[q]);
+                    row[p] = c * x - s * y;
+                    row[q] = s * x + c * y;
+                }
+                let (upper, lower) = a.split_at_mut(q);
+                for (x, y) in upper[p].iter_mut().zip(lower[0].iter_mut()) {
+                    let (xp, yq) = (*x, *y);
+                    *x = c * xp - s * yq;
+                    *y = s * xp + c * yq;
+                }
+            }
+        }
+    }
+    let mut order = (0..n).collect::<Vec<usize>>();
+    order.sort_by(|&i, &j| a[j][j].total_cmp(&a[i][i]));
+    let values = order.iter().map(|&i| a[i][i]).collect();
+    let vectors = order.iter().map(|&i| v.iter().map(|row| row[i]).collect()).collect();
+    (values, vectors)
+}
+
+/// Eigenvalues of a general square matrix as pairs of real and imaginary parts
+///
+/// The characteristic polynomial is computed by the Faddeev–LeVerrier algorithm and its roots by
+/// the Durand–Kerner iteration. This is accurate only for small, well-conditioned matrices.
+pub(crate) fn eigenvalues(a: &[Vec<f64>]) -> Vec<(f64, f64)> {
+    let n = a.len();
+    // coefficients[k] is the coefficient of z^k of the monic characteristic polynomial
+    let mut coefficients = vec![0.0; n+1];
+    coefficients[n] = 1.0;
+    let mut m = vec![vec![0.0; n]; n];
+    for k in 1..=n {
+        let mut next = multiply(a, &m);
+        for (i, row) in next.iter_mut().enumerate() {
+            row[i] += coefficients[n+1-k];
+        }
+        let am = multiply(a, &next);
+        coefficients[n-k] = -(0..n).map(|i| am[i][i]).sum::<f64>() / k as f64;
+        m = next;
+    }
+    let eval = |z: (f64, f64)| coefficients.iter().rev().fold((0.0, 0.0), |acc, &c| {
+        let (re, im) = complex_mul(acc, z);
+        (re + c, im)
+    });
+    let mut roots = (0..n)
+        .scan((1.0, 0.0), |z, _| {
+            *z = complex_mul(*z, (0.4, 0.9));
+            Some(*z)
+        })
+        .collect::<Vec<_>>();
+    for _ in 0..1000 {
+        let mut change = 0.0f64;
+        for k in 0..n {
+            let denominator = (0..n)
+                .filter(|&j| j != k)
+                .fold((1.0, 0.0), |acc, j| complex_mul(acc, (roots[k].0 - roots[j].0, roots[k].1 - roots[j].1)));
+            let step = complex_div(eval(roots[k]), denominator);
+            roots[k] = (roots[k].0 - step.0, roots[k].1 - step.1);
+            change = change.max(step.0.hypot(step.1));
+        }
+        if change < 1e-14 {
+            break;
+        }
+    }
+    roots
+}
+
+/// Unit eigenvector of a general square matrix for one of its eigenvalues
+///
+/// The eigenvector is computed by inverse iteration with the shift slightly off `value`, so
+/// `value` should be accurate, e.g. from `eigenvalues`. Its phase is arbitrary.
+pub(crate) fn eigenvector(a: &[Vec<f64>], value: (f64, f64)) -> Vec<(f64, f64)> {
+    let n = a.len();
+    let offset = 1e-10 * (1.0 + value.0.hypot(value.1));
+    let shift = (value.0 + offset, value.1);
+    let mut v = (0..n).map(|i| (1.0, 0.1 * i as f64)).collect::<Vec<_>>();
+    for _ in 0..3 {
+        let mut m = a.iter()
+            .enumerate()
+            .map(|(i, row)| {
+                row.iter()
+                    .enumerate()
+                    .map(|(j, &x)| if i == j { (x - shift.0, -shift.1) } else { (x, 0.0) })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        // Gaussian elimination with partial pivoting on the complex matrix
+        for col in 0..n {
+            let pivot = (col..n).max_by(|&i, &j| m[i][col].0.hypot(m[i][col].1).total_cmp(&m[j][col].0.hypot(m[j][col].1))).unwrap();
+            m.swap(col, pivot);
+            v.swap(col, pivot);
+            if m[col][col] == (0.0, 0.0) {
+                m[col][col] = (offset, 0.0);
+            }
+            for row in col+1..n {
+                let factor = complex_div(m[row][col], m[col][col]);
+                let (upper, lower) = m.split_at_mut(row);
+                for (x, &p) in lower[0].iter_mut().zip(upper[col].iter()).skip(col) {
+                    let (re, im) = complex_mul(factor, p);
+                    *x = (x.0 - re, x.1 - im);
+                }
+                let (re, im) = complex_mul(factor, v[col]);
+                v[row] = (v[row].0 - re, v[row].1 - im);
+            }
+        }
+        for row in (0..n).rev() {
+            let mut x = v[row];
+            for k in row+1..n {
+                let (re, im) = complex_mul(m[row][k], v[k]);
+                x = (x.0 - re, x.1 - im);
+            }
+            v[row] = complex_div(x, m[row][row]);
+        }
+        let norm = v.iter().map(|x| x.0 * x.0 + x.1 * x.1).sum::<f64>().sqrt();
+        for x in v.iter_mut() {
+            *x = (x.0 / norm, x.1 / norm);
+        }
+    }
+    v
+}
+
+fn multiply(a: &[Vec<f64>], b: &[Vec<f64>]) -> Vec<Vec<f64>> {
+    a.iter()
+        .map(|row| (0..b.len()).map(|j| row.iter().zip(b.iter()).map(|(x, b_row)| x * b_row[j]).sum()).collect())
+        .collect()
+}
+
+fn complex_mul(a: (f64, f64), b: (f64, f64)) -> (f64, f64) {
+    (a.0 * b.0 - a.1 * b.1, a.0 * b.1 + a.1 * b.0)
+}
+
+fn complex_div(a: (f64, f64), b: (f64, f64)) -> (f64, f64) {
+    let norm = b.0 * b.0 + b.1 * b.1;
+    ((a.0 * b.0 + a.1 * b.1) / norm, (a.1 * b.0 - a.0 * b.1) / norm)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::linalg::{eigenvalues, eigenvector, least_squares, solve, symmetric_eigen};
+
+    #[test]
+    fn test_solve() {
+        let a = vec![vec![0.0, 2.0, 1.0], vec![1.0, 1.0, 0.0], vec![3.0, 0.0, 1.0]];
+        let x = solve(a, vec![7.0, 3.0, 6.0]).unwrap();
+        for (xi, ei) in x.iter().zip([1.0, 2.0, 3.0].iter()) {
+            assert!((xi - ei).abs() < 1e-12);
+        }
+        assert_eq!(solve(vec![vec![1.0, 2.0], vec![2.0, 4.0]], vec![1.0, 2.0]), None);
+        let beta = least_squares(&[vec![1.0, 0.0], vec![1.0, 1.0], vec![1.0, 2.0]], &[1.0, 3.0, 5.0]).unwrap();
+        assert!((beta[0] - 1.0).abs() < 1e-12 && (beta[1] - 2.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_symmetric_eigen() {
+        let a = vec![vec![2.0, 1.0, 0.0], vec![1.0, 2.0, 0.0], vec![0.0, 0.0, 5.0]];
+        let (values, vectors) = symmetric_eigen(a.clone());
+        for (value, expected) in values.iter().zip([5.0, 3.0, 1.0].iter()) {
+            assert!((value - expected).abs() < 1e-12);
+        }
+        for (value, vector) in values.iter().zip(vectors.iter()) {
+            for (row, x) in a.iter().zip(vector.iter()) {
+                let av = row.iter().zip(vector.iter()).map(|(r, v)| r * v).sum::<f64>();
+                assert!((av - value * x).abs() < 1e-12);
+            }
+            assert!((vector.iter().map(|x| x * x).sum::<f64>() - 1.0).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_eigenvalues() {
+        // A rotation by 0.5 rad scaled by 0.9, and a real eigenvalue 0.5
+        let (c, s) = (0.9 * 0.5f64.cos(), 0.9 * 0.5f64.sin());
+        let a = vec![vec![c, -s, 0.0], vec![s, c, 1.0], vec![0.0, 0.0, 0.5]];
+        let mut values = eigenvalues(&a);
+        values.sort_by(|x, y| y.1.total_cmp(&x.1));
+        for (value, expected) in values.iter().zip([(c, s), (0.5, 0.0), (c, -s)].iter()) {
+            assert!((value.0 - expected.0).abs() < 1e-10 && (value.1 - expected.1).abs() < 1e-10);
+        }
+        for &value in values.iter() {
+            let v = eigenvector(&a, value);
+            for (row, x) in a.iter().zip(v.iter()) {
+                let av = row.iter().zip(v.iter()).fold((0.0, 0.0), |acc, (r, y)| (acc.0 + r * y.0, acc.1 + r * y.1));
+                let lambda_x = (value.0 * x.0 - value.1 * x.1, value.0 * x.1 + value.1 * x.0);
+                assert!((av.0 - lambda_x.0).abs() < 1e-8 && (av.1 - lambda_x.1).abs() < 1e-8);
+            }
+        }
+    }
+}
@@ -1,5 +1,8 @@
-use clap::{App, Arg};
-use delay_coord::{DelayCoordinates, ForwardDelayCoordinates};
+use clap::{App, Arg, ArgMatches, SubCommand};
+use delay_coord::{CoordKind, DelayCoordinates, ForwardDelayCoordinates};
+use delay_coord::dimension::correlation_dimension_of_points;
+use delay_coord::estimate::{fnn_dimension, mutual_information_delay};
+use delay_coord::metric::Metric;
 
 fn open_file_or_stdin<'a, T: AsRef<::std::path::Path>>(
     path: &Option<T>,
@@ -15,22 +18,371 @@ fn open_file_or_stdin<'a, T: AsRef<::std::path::Path>>(
     }
 }
 
-fn read_data_file<R: ::std::io::BufRead>(reader: &mut R) -> Vec<Vec<f64>> {
+fn read_data_file<R: ::std::io::BufRead>(reader: &mut R, max_rows: Option<usize>) -> Vec<Vec<f64>> {
     let mut data = Vec::new();
-    loop {
+    while max_rows.is_none_or(|max| data.len() < max) {
         let mut buf = String::new();
         let size = reader.read_line(&mut buf).unwrap();
         if size == 0 {
             break;
         }
-        let v: Vec<f64> = buf.trim().split(',')
-                             .map(|s| s.parse::<f64>().unwrap())
-                             .collect();
-        data.push(v);
+        data.push(parse_row(&buf));
     }
     data
 }
 
+fn parse_row(line: &str) -> Vec<f64> {
+    line.trim().split(',')
+        .map(|s| s.parse::<f64>().unwrap())
+        .collect()
+}
+
+/// Reads all rows of the input, parsing them on several threads
+///
+/// The input is read at once and its lines are split into a contiguous block for each thread,
+/// so the rows are in the same order as `read_data_file`.
+#[cfg(feature = "parallel")]
+fn read_data_file_parallel<R: ::std::io::Read>(reader: &mut R, threads: usize) -> Vec<Vec<f64>> {
+    let mut text = String::new();
+    reader.read_to_string(&mut text).unwrap();
+    let lines = text.lines().collect::<Vec<&str>>();
+    let block = lines.len().div_ceil(threads.max(1)).max(1);
+    ::std::thread::scope(|scope| {
+        let handles = lines.chunks(block)
+            .map(|block| scope.spawn(move || block.iter().map(|line| parse_row(line)).collect::<Vec<_>>()))
+            .collect::<Vec<_>>();
+        handles.into_iter()
+            .flat_map(|handle| handle.join().unwrap())
+            .collect()
+    })
+}
+
+/// Reads all rows of the input, on all available threads with the `parallel` feature
+fn read_all_rows<R: ::std::io::BufRead>(reader: &mut R) -> Vec<Vec<f64>> {
+    #[cfg(feature = "parallel")]
+    {
+        let threads = ::std::thread::available_parallelism().map_or(1, |n| n.get());
+        read_data_file_parallel(reader, threads)
+    }
+    #[cfg(not(feature = "parallel"))]
+    {
+        read_data_file(reader, None)
+    }
+}
+
+/// Transposes data stored with a variable in each row into a sample in each row
+fn transpose(data: Vec<Vec<f64>>) -> Result<Vec<Vec<f64>>, String> {
+    let len = data.first().map_or(0, |row| row.len());
+    if let Some(i) = data.iter().position(|row| row.len() != len) {
+        return Err(format!("Row {} has {} values while row 0 has {}", i, data[i].len(), len));
+    }
+    Ok((0..len).map(|t| data.iter().map(|row| row[t]).collect()).collect())
+}
+
+/// Output format of the embedded vectors
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    /// Comma-separated values, one vector in each row
+    Csv,
+    /// JSON Lines, one array of numbers in each line
+    ///
+    /// Non-finite values are written as `null` since JSON has no representation of them.
+    Jsonl,
+}
+
+impl ::std::str::FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "csv" => Ok(Format::Csv),
+            "jsonl" => Ok(Format::Jsonl),
+            _ => Err(format!("Unknown format: {}", s)),
+        }
+    }
+}
+
+/// Splits the column `col` of the data off as timestamps
+///
+/// Returns the timestamps and the remaining values.
+/// The timestamps must increase strictly, but they need not be uniformly spaced.
+fn split_time_column(data: Vec<Vec<f64>>, col: usize) -> Result<(Vec<f64>, Vec<Vec<f64>>), String> {
+    let mut times = Vec::with_capacity(data.len());
+    let mut values = Vec::with_capacity(data.len());
+    for (i, mut row) in data.into_iter().enumerate() {
+        if col >= row.len() {
+            return Err(format!("Row {} has no time column {}", i, col));
+        }
+        let t = row.remove(col);
+        if let Some(&prev) = times.last() {
+            if t <= prev {
+                return Err(format!("Timestamp {} in row {} does not increase from {}", t, i, prev));
+            }
+        }
+        times.push(t);
+        values.push(row);
+    }
+    Ok((times, values))
+}
+
+/// Writes vectors as rows in the given format
+fn write_rows<W, I>(writer: &mut W, rows: I, format: Format) -> ::std::io::Result<()>
+where
+    W: ::std::io::Write,
+    I: Iterator<Item = Vec<f64>>,
+{
+    for v in rows {
+        let fields = v.iter().map(|x| match format {
+            Format::Jsonl if !x.is_finite() => "null".to_string(),
+            _ => x.to_string(),
+        });
+        write_line(writer, fields, format)?;
+    }
+    Ok(())
+}
+
+/// Writes the column names in the given format
+fn write_header<W>(writer: &mut W, names: Vec<String>, format: Format) -> ::std::io::Result<()>
+where
+    W: ::std::io::Write,
+{
+    match format {
+        Format::Csv => write_line(writer, names.into_iter(), format),
+        Format::Jsonl => write_line(writer, names.iter().map(|name| format!("\"{}\"", name)), format),
+    }
+}
+
+/// Writes a line of fields, which are already formatted
+fn write_line<W, I>(writer: &mut W, fields: I, format: Format) -> ::std::io::Result<()>
+where
+    W: ::std::io::Write,
+    I: Iterator<Item = String>,
+{
+    let line = fields.collect::<Vec<_>>().join(",");
+    match format {
+        Format::Csv => writeln!(writer, "{}", line),
+        Format::Jsonl => writeln!(writer, "[{}]", line),
+    }
+}
+
+/// Largest number of characters of a sparkline
+const SPARKLINE_WIDTH: usize = 80;
+
+/// Writes a sparkline of each coordinate across the windows
+///
+/// Each line is the column name of the coordinate followed by unicode block characters scaled
+/// between the minimum and the maximum of the coordinate. Runs of consecutive windows are
+/// averaged so that a line has at most `SPARKLINE_WIDTH` characters.
+fn write_sparklines<W, C>(writer: &mut W, coord: &C, data: &[Vec<f64>]) -> ::std::io::Result<()>
+where
+    W: ::std::io::Write,
+    C: DelayCoordinates,
+{
+    const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    let rows = coord.mapping_iter(data).map(|p| p.to_flatten_vec()).collect::<Vec<_>>();
+    let names = header(coord, data.first().map_or(0, |row| row.len()));
+    let bucket = rows.len().div_ceil(SPARKLINE_WIDTH).max(1);
+    for (k, name) in names.iter().enumerate() {
+        let values = rows.chunks(bucket)
+            .map(|chunk| chunk.iter().map(|row| row[k]).sum::<f64>() / chunk.len() as f64)
+            .collect::<Vec<f64>>();
+        let min = values.iter().cloned().fold(f64::INFINITY, f64::min);
+        let max = values.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+        let line = values.iter()
+            .map(|&x| {
+                let level = if max > min { ((x - min) / (max - min) * 7.0).round() as usize } else { 0 };
+                BARS[level.min(7)]
+            })
+            .collect::<String>();
+        writeln!(writer, "{} {}", name, line)?;
+    }
+    Ok(())
+}
+
+/// Writes the embedded vectors as columns ordered from the oldest to the newest sample
+///
+/// Each line has the coordinates of a channel from the oldest to the newest sample, followed by
+/// those of the next channel, separated by spaces. The numbers are formatted as `%e` of C.
+/// The layout is meant to follow the `delay` program of TISEAN, but it has not been checked
+/// against the output of that program.
+fn write_tisean<W, C>(writer: &mut W, coord: &C, data: &[Vec<f64>]) -> ::std::io::Result<()>
+where
+    W: ::std::io::Write,
+    C: DelayCoordinates,
+{
+    let channels = data.first().map_or(0, |row| row.len());
+    let mut order = (0..coord.dimension()).collect::<Vec<usize>>();
+    let lags = coord.describe()
+        .iter()
+        .map(|info| match info.kind {
+            CoordKind::Delay => info.lag as isize,
+            CoordKind::Lead => -(info.lag as isize),
+        })
+        .collect::<Vec<isize>>();
+    order.sort_by(|&a, &b| lags[b].cmp(&lags[a]));
+    for view in coord.mapping_iter(data) {
+        let v = view.to_flatten_vec();
+        let line = (0..channels)
+            .flat_map(|c| order.iter().map(move |&k| k * channels + c))
+            .map(|i| format_c_exponent(v[i]))
+            .collect::<Vec<_>>()
+            .join(" ");
+        writeln!(writer, "{}", line)?;
+    }
+    Ok(())
+}
+
+/// Formats a number like `%e` of C, e.g. `-1.250000e-03`
+fn format_c_exponent(x: f64) -> String {
+    if x.is_nan() {
+        return "nan".to_string();
+    }
+    if x.is_infinite() {
+        return if x > 0.0 { "inf" } else { "-inf" }.to_string();
+    }
+    let formatted = format!("{:.6e}", x);
+    let (mantissa, exponent) = formatted.split_once('e').unwrap();
+    let exponent = exponent.parse::<i32>().unwrap();
+    format!("{}e{}{:02}", mantissa, if exponent < 0 { '-' } else { '+' }, exponent.abs())
+}
+
+/// Writes the raw window and the embedded vector of each window for debugging
+///
+/// Each line has the form `window: w_0,w_1,... | coords: c_0,c_1,...`, where the window is the
+/// flattened samples of the window in time order and the coordinates are the embedded vector.
+fn write_debug_windows<W, C>(writer: &mut W, coord: &C, data: &[Vec<f64>]) -> ::std::io::Result<()>
+where
+    W: ::std::io::Write,
+    C: DelayCoordinates,
+{
+    let join = |v: &[f64]| v.iter().map(|x| x.to_string()).collect::<Vec<_>>().join(",");
+    for view in coord.mapping_iter(data) {
+        let window = view.window_slice().concat();
+        writeln!(writer, "window: {} | coords: {}", join(&window), join(&view.to_flatten_vec()))?;
+    }
+    Ok(())
+}
+
+/// Column names of the embedded vectors of samples with `channels` channels
+fn header<C: DelayCoordinates>(coord: &C, channels: usize) -> Vec<String> {
+    coord.describe()
+         .iter()
+         .flat_map(|info| {
+             let prefix = match info.kind {
+                 CoordKind::Delay => "lag",
+                 CoordKind::Lead => "lead",
+             };
+             (0..channels).map(move |c| format!("ch{}_{}{}", c, prefix, info.lag))
+         })
+         .collect()
+}
+
+/// Embeds the input chunk by chunk and writes the embedded vectors
+///
+/// Each chunk appends at most `chunk_size` rows to the last `window_size - 1` rows of the
+/// previous chunk, so the output does not depend on the chunk size.
+/// Reading stops as soon as `limit` windows are written.
+/// If `with_header` is true, the column names are written before the first row.
+fn embed_chunked<R, W, C>(reader: &mut R, writer: &mut W, coord: &C, chunk_size: usize, limit: Option<usize>,
+                          mut with_header: bool, format: Format) -> ::std::io::Result<()>
+where
+    R: ::std::io::BufRead,
+    W: ::std::io::Write,
+    C: DelayCoordinates,
+{
+    let overlap = coord.window_size() - 1;
+    let mut remaining = limit;
+    let mut data = Vec::new();
+    while remaining != Some(0) {
+        let to_read = match remaining {
+            Some(n) => chunk_size.min(overlap + n - data.len()),
+            None => chunk_size,
+        };
+        let chunk = read_data_file(reader, Some(to_read));
+        if chunk.is_empty() {
+            break;
+        }
+        if with_header {
+            write_header(writer, header(coord, chunk[0].len()), format)?;
+            with_header = false;
+        }
+        data.extend(chunk);
+        let windows = (data.len() + 1).saturating_sub(coord.window_size());
+        let windows = remaining.map_or(windows, |n| windows.min(n));
+        write_rows(writer, coord.mapping_iter(&data).take(windows).map(|p| p.to_flatten_vec()), format)?;
+        remaining = remaining.map(|n| n - windows);
+        let consumed = data.len().saturating_sub(overlap);
+        data.drain(..consumed);
+    }
+    Ok(())
+}
+
+/// Number of leading samples the estimators of `--check` are run on
+const CHECK_SAMPLES: usize = 2000;
+
+/// Compares embedding parameters with the recommendations of the estimators
+///
+/// The delay is compared with the first minimum of the mutual information and the dimension with
+/// the false nearest neighbors method at the given delay. Returns a warning for each parameter
+/// that deviates substantially: a delay outside half to twice the recommended one or a dimension
+/// below the recommended one.
+fn check_parameters(series: &[f64], delay: usize, dimension: usize) -> Vec<String> {
+    let series = &series[..series.len().min(CHECK_SAMPLES)];
+    let mut warnings = Vec::new();
+    let recommended = match mutual_information_delay(series, (series.len() / 4).min(50), 16) {
+        Ok(recommended) => recommended,
+        Err(e) => return vec![format!("Parameters cannot be checked: {}", e)],
+    };
+    if 2 * delay < recommended || delay > 2 * recommended {
+        warnings.push(format!("Delay {} deviates from {} recommended by mutual information", delay, recommended));
+    }
+    if let Ok(recommended) = fnn_dimension(series, delay, 10, 10.0, 0.05) {
+        if dimension < recommended {
+            warnings.push(format!("Dimension {} is below {} recommended by false nearest neighbors",
+                                  dimension, recommended));
+        }
+    }
+    warnings
+}
+
+/// Pairs $(x(t), x(t+\\tau))$ of the lagged scatter plot of a series
+fn lagged_pairs(series: &[f64], delay: usize) -> impl Iterator<Item = Vec<f64>> + '_ {
+    series.iter()
+          .zip(series.iter().skip(delay))
+          .map(|(&x, &y)| vec![x, y])
+}
+
+/// Runs the `dimension` subcommand
+///
+/// It prints the correlation dimension of the embedded input and its scaling range.
+fn run_dimension(matches: &ArgMatches) {
+    let dimension = matches.value_of("dimension")
+                           .unwrap()
+                           .parse::<usize>()
+                           .expect("Embedding dimension must be usize");
+    let delay = matches.value_of("delay")
+                       .unwrap()
+                       .parse::<usize>()
+                       .expect("Delay must be usize");
+    let theiler = matches.value_of("theiler")
+                         .unwrap()
+                         .parse::<usize>()
+                         .expect("Theiler window must be usize");
+    let input = matches.value_of("INPUT");
+    let stdin = ::std::io::stdin();
+    let mut input = open_file_or_stdin(&input, &stdin);
+    let data = read_all_rows(&mut input);
+    let coord = ForwardDelayCoordinates {
+            dimension,
+            delay,
+    };
+    let points = coord.mapping_iter(&data).map(|p| p.to_flatten_vec()).collect::<Vec<_>>();
+    let estimate = correlation_dimension_of_points(&points, Metric::Euclidean, theiler)
+        .expect("Too few distinct points to estimate the correlation dimension");
+    println!("dimension {}", estimate.dimension);
+    println!("scaling_range {} {}", estimate.scaling_range.0, estimate.scaling_range.1);
+}
+
 fn main() {
     let matches = App::new("delay-coordinate mapping")
                           .version("0.1.0")
@@ -48,33 +400,377 @@ fn main() {
                                .value_name("DIM")
                                .help("Sets the embedding dimension")
                                .takes_value(true))
+                          .arg(Arg::with_name("limit")
+                               .short("n")
+                               .long("limit")
+                               .value_name("N")
+                               .help("Stops after N windows")
+                               .takes_value(true))
+                          .arg(Arg::with_name("chunk-size")
+                               .long("chunk-size")
+                               .value_name("ROWS")
+                               .help("Sets the number of rows read at once (default: 4096)")
+                               .takes_value(true))
+                          .arg(Arg::with_name("header")
+                               .long("header")
+                               .help("Writes a row of column names first"))
+                          .arg(Arg::with_name("format")
+                               .long("format")
+                               .value_name("FORMAT")
+                               .help("Sets the output format")
+                               .possible_values(&["csv", "jsonl"])
+                               .default_value("csv")
+                               .takes_value(true))
+                          .arg(Arg::with_name("scatter")
+                               .long("scatter")
+                               .help("Writes the pairs (x(t), x(t+DELAY)) of the first column instead of embedding"))
+                          .arg(Arg::with_name("time-col")
+                               .long("time-col")
+                               .value_name("COL")
+                               .help("Reads the column COL as timestamps and writes the time of each window first")
+                               .takes_value(true))
+                          .arg(Arg::with_name("check")
+                               .long("check")
+                               .help("Warns if the delay or the dimension deviates from the estimators' recommendations"))
+                          .arg(Arg::with_name("debug-windows")
+                               .long("debug-windows")
+                               .help("Writes the raw window before the coordinates of each window"))
+                          .arg(Arg::with_name("sparkline")
+                               .long("sparkline")
+                               .help("Prints a sparkline of each coordinate across time instead of the coordinates"))
+                          .arg(Arg::with_name("tisean")
+                               .long("tisean")
+                               .help("Prints the coordinates from the oldest to the newest sample as %e columns, channel by channel"))
+                          .arg(Arg::with_name("transpose")
+                               .long("transpose")
+                               .help("Reads each variable from a row instead of a column"))
                           .arg(Arg::with_name("INPUT")
                                .help("Sets the input file")
                                .index(1))
+                          .subcommand(SubCommand::with_name("dimension")
+                               .about("Estimates the correlation dimension of the embedded input")
+                               .arg(Arg::with_name("delay")
+                                    .short("d")
+                                    .long("delay")
+                                    .value_name("DELAY")
+                                    .help("Sets the delay in steps")
+                                    .required(true)
+                                    .takes_value(true))
+                               .arg(Arg::with_name("dimension")
+                                    .short("m")
+                                    .long("dimension")
+                                    .value_name("DIM")
+                                    .help("Sets the embedding dimension")
+                                    .required(true)
+                                    .takes_value(true))
+                               .arg(Arg::with_name("theiler")
+                                    .long("theiler")
+                                    .value_name("STEPS")
+                                    .help("Excludes pairs of points at most STEPS apart in time")
+                                    .default_value("0")
+                                    .takes_value(true))
+                               .arg(Arg::with_name("INPUT")
+                                    .help("Sets the input file")
+                                    .index(1)))
                           .get_matches();
 
-    let dimension = matches.value_of("dimension")
-                           .expect("Embedding dimension must be specified")
-                           .parse::<usize>()
-                           .expect("Embedding dimension must be usize");
+    if let Some(matches) = matches.subcommand_matches("dimension") {
+        run_dimension(matches);
+        return;
+    }
+
     let delay = matches.value_of("delay")
                        .expect("Delay must be specified")
                        .parse::<usize>()
                        .expect("Delay must be usize");
+    let limit = matches.value_of("limit")
+                       .map(|s| s.parse::<usize>().expect("Limit must be usize"));
+    let chunk_size = matches.value_of("chunk-size")
+                            .map_or(4096, |s| s.parse::<usize>().expect("Chunk size must be usize"));
+    assert!(chunk_size > 0, "Chunk size must be positive");
+    let time_col = matches.value_of("time-col")
+                          .map(|s| s.parse::<usize>().expect("Time column must be usize"));
+    let format = matches.value_of("format")
+                        .unwrap()
+                        .parse::<Format>()
+                        .unwrap_or_else(|e| panic!("{}", e));
 
     let input = matches.value_of("INPUT");
     let stdin = ::std::io::stdin();
     let mut input = open_file_or_stdin(&input, &stdin);
-    let data = read_data_file(&mut input);
+    let stdout = ::std::io::stdout();
+    let mut output = ::std::io::BufWriter::new(stdout.lock());
+
+    if matches.is_present("scatter") {
+        let series = read_data_file(&mut input, limit.map(|n| n + delay))
+            .iter()
+            .map(|row| row[0])
+            .collect::<Vec<f64>>();
+        write_rows(&mut output, lagged_pairs(&series, delay), format)
+            .expect("Failed to write the output");
+        return;
+    }
 
+    let dimension = matches.value_of("dimension")
+                           .expect("Embedding dimension must be specified")
+                           .parse::<usize>()
+                           .expect("Embedding dimension must be usize");
     let coord = ForwardDelayCoordinates {
-            dimension: dimension,
-            delay: delay,
+            dimension,
+            delay,
     };
-    for v in coord.mapping_iter(&data).map(|p| p.to_flatten_vec()) {
-        for i in 0..v.len() {
-            let delim = if i == v.len()-1 { '\n' } else { ',' };
-            print!("{}{}", v[i], delim);
+    if matches.is_present("transpose") || time_col.is_some() || matches.is_present("check")
+        || matches.is_present("debug-windows") || matches.is_present("sparkline")
+        || matches.is_present("tisean") {
+        let mut data = read_all_rows(&mut input);
+        if matches.is_present("transpose") {
+            data = transpose(data).unwrap_or_else(|e| panic!("Input cannot be transposed: {}", e));
+        }
+        let times = time_col.map(|col| {
+            let (times, values) = split_time_column(::std::mem::take(&mut data), col)
+                .unwrap_or_else(|e| panic!("Invalid time column: {}", e));
+            data = values;
+            times
+        });
+        if matches.is_present("check") {
+            let series = data.iter().map(|row| row[0]).collect::<Vec<f64>>();
+            for warning in check_parameters(&series, delay, dimension) {
+                eprintln!("warning: {}", warning);
+            }
+        }
+        data.truncate(limit.map_or(data.len(), |n| coord.len_for_windows(n)));
+        if matches.is_present("debug-windows") {
+            write_debug_windows(&mut output, &coord, &data).expect("Failed to write the output");
+            return;
+        }
+        if matches.is_present("sparkline") {
+            write_sparklines(&mut output, &coord, &data).expect("Failed to write the output");
+            return;
+        }
+        if matches.is_present("tisean") {
+            write_tisean(&mut output, &coord, &data).expect("Failed to write the output");
+            return;
+        }
+        if matches.is_present("header") {
+            let mut names = header(&coord, data.first().map_or(0, |row| row.len()));
+            if times.is_some() {
+                names.insert(0, "time".to_string());
+            }
+            write_header(&mut output, names, format).expect("Failed to write the output");
         }
+        let last = coord.window_size() - 1;
+        let rows = coord.mapping_iter(&data)
+            .enumerate()
+            .map(|(i, p)| match &times {
+                Some(times) => std::iter::once(times[i+last]).chain(p.to_flatten_vec()).collect(),
+                None => p.to_flatten_vec(),
+            });
+        write_rows(&mut output, rows, format).expect("Failed to write the output");
+    } else {
+        embed_chunked(&mut input, &mut output, &coord, chunk_size, limit, matches.is_present("header"), format)
+            .expect("Failed to write the output");
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{Format, embed_chunked, header, lagged_pairs, read_data_file, transpose, write_rows};
+    use crate::{check_parameters, split_time_column, write_debug_windows, write_sparklines, write_tisean};
+    use delay_coord::{DelayCoordinates, ForwardDelayCoordinates, MixedDelayCoordinates};
+
+    #[test]
+    fn test_limit_windows() {
+        let text = (0..100).map(|n| format!("{}\n", n)).collect::<String>();
+        let mut reader = ::std::io::Cursor::new(text);
+        let coord = ForwardDelayCoordinates {
+            delay: 3,
+            dimension: 4,
+        };
+        let data = read_data_file(&mut reader, Some(coord.len_for_windows(5)));
+        assert_eq!(data.len(), 14);
+        assert_eq!(coord.mapping_iter(&data).count(), 5);
+    }
+
+    #[test]
+    fn test_transpose() {
+        let mut reader = ::std::io::Cursor::new("0,1,2,3\n10,11,12,13\n");
+        let data = transpose(read_data_file(&mut reader, None)).unwrap();
+        assert_eq!(data, vec![vec![0.0, 10.0], vec![1.0, 11.0], vec![2.0, 12.0], vec![3.0, 13.0]]);
+
+        let mut reader = ::std::io::Cursor::new("0,1,2,3\n10,11,12\n");
+        assert!(transpose(read_data_file(&mut reader, None)).is_err());
+    }
+
+    #[test]
+    fn test_chunk_size() {
+        let text = (0..50).map(|n| format!("{},{}\n", n, n * n)).collect::<String>();
+        let coord = ForwardDelayCoordinates {
+            delay: 3,
+            dimension: 3,
+        };
+        let embed = |chunk_size, limit| {
+            let mut reader = ::std::io::Cursor::new(text.as_str());
+            let mut output = Vec::new();
+            embed_chunked(&mut reader, &mut output, &coord, chunk_size, limit, false, Format::Csv).unwrap();
+            String::from_utf8(output).unwrap()
+        };
+        let expected = embed(4096, None);
+        assert_eq!(expected.lines().count(), 44);
+        assert_eq!(expected.lines().next(), Some("6,36,3,9,0,0"));
+        for &chunk_size in [1, 2, 7, 44, 50].iter() {
+            assert_eq!(embed(chunk_size, None), expected);
+        }
+        let limited = embed(5, Some(10));
+        assert_eq!(limited.lines().count(), 10);
+        assert!(expected.starts_with(&limited));
+    }
+
+    #[test]
+    fn test_header() {
+        let coord = ForwardDelayCoordinates {
+            delay: 2,
+            dimension: 3,
+        };
+        assert_eq!(header(&coord, 1), vec!["ch0_lag0", "ch0_lag2", "ch0_lag4"]);
+        let mut reader = ::std::io::Cursor::new("1,10\n2,20\n3,30\n4,40\n5,50\n");
+        let mut output = Vec::new();
+        embed_chunked(&mut reader, &mut output, &coord, 2, None, true, Format::Csv).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let mut lines = output.lines();
+        assert_eq!(lines.next(), Some("ch0_lag0,ch1_lag0,ch0_lag2,ch1_lag2,ch0_lag4,ch1_lag4"));
+        assert_eq!(lines.next(), Some("5,50,3,30,1,10"));
+        assert_eq!(lines.next(), None);
+        let centered = MixedDelayCoordinates {
+            lags: vec![-1, 0, 1],
+        };
+        assert_eq!(header(&centered, 1), vec!["ch0_lead1", "ch0_lag0", "ch0_lag1"]);
+    }
+
+    #[test]
+    fn test_format_jsonl() {
+        let text = (0..20).map(|n| format!("{},{}\n", n, -n)).collect::<String>();
+        let coord = ForwardDelayCoordinates {
+            delay: 2,
+            dimension: 3,
+        };
+        let mut reader = ::std::io::Cursor::new(text);
+        let mut output = Vec::new();
+        embed_chunked(&mut reader, &mut output, &coord, 3, None, true, Format::Jsonl).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let mut lines = output.lines();
+        assert_eq!(lines.next(), Some(r#"["ch0_lag0","ch1_lag0","ch0_lag2","ch1_lag2","ch0_lag4","ch1_lag4"]"#));
+        let rows = lines
+            .map(|line| {
+                assert!(line.starts_with('[') && line.ends_with(']'));
+                line[1..line.len()-1].split(',').map(|x| x.parse::<f64>().unwrap()).collect::<Vec<f64>>()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(rows.len(), 16);
+        assert!(rows.iter().all(|row| row.len() == 6));
+        assert_eq!(rows[0], vec![4.0, -4.0, 2.0, -2.0, 0.0, 0.0]);
+
+        let mut output = Vec::new();
+        write_rows(&mut output, vec![vec![1.5, f64::NAN]].into_iter(), Format::Jsonl).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "[1.5,null]\n");
+    }
+
+    #[test]
+    fn test_lagged_pairs() {
+        let series = [1.0, 2.0, 4.0, 8.0, 16.0];
+        let pairs = lagged_pairs(&series, 2).collect::<Vec<_>>();
+        assert_eq!(pairs, vec![vec![1.0, 4.0], vec![2.0, 8.0], vec![4.0, 16.0]]);
+        assert_eq!(lagged_pairs(&series, 5).count(), 0);
+        let mut output = Vec::new();
+        write_rows(&mut output, lagged_pairs(&series, 3), Format::Csv).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "1,8\n2,16\n");
+    }
+
+    #[test]
+    fn test_time_column() {
+        let mut reader = ::std::io::Cursor::new("0.0,1,10\n0.5,2,20\n2.0,3,30\n");
+        let (times, values) = split_time_column(read_data_file(&mut reader, None), 0).unwrap();
+        assert_eq!(times, vec![0.0, 0.5, 2.0]);
+        assert_eq!(values, vec![vec![1.0, 10.0], vec![2.0, 20.0], vec![3.0, 30.0]]);
+
+        let mut reader = ::std::io::Cursor::new("0.0,1\n0.5,2\n0.5,3\n");
+        assert!(split_time_column(read_data_file(&mut reader, None), 0).is_err());
+        let mut reader = ::std::io::Cursor::new("0.0,1\n");
+        assert!(split_time_column(read_data_file(&mut reader, None), 2).is_err());
+    }
+
+    #[test]
+    fn test_check_parameters() {
+        let series = (0..1000).map(|t| (t as f64 / 5.0).sin()).collect::<Vec<f64>>();
+        assert!(check_parameters(&series, 8, 3).is_empty());
+        let warnings = check_parameters(&series, 1, 1);
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[0].starts_with("Delay 1"));
+        assert!(warnings[1].starts_with("Dimension 1"));
+        assert_eq!(check_parameters(&[1.0; 100], 1, 1).len(), 1);
+    }
+
+    #[test]
+    fn test_debug_windows() {
+        let data = (0..6).map(|t| vec![t as f64, 10.0 * t as f64]).collect::<Vec<_>>();
+        let coord = ForwardDelayCoordinates {
+            delay: 2,
+            dimension: 2,
+        };
+        let mut output = Vec::new();
+        write_debug_windows(&mut output, &coord, &data).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let lines = output.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[0], "window: 0,0,1,10,2,20 | coords: 2,20,0,0");
+        assert!(lines.iter().all(|line| line.starts_with("window: ") && line.contains(" | coords: ")));
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_read_data_file_parallel() {
+        use crate::read_data_file_parallel;
+
+        let text = (0..1000).map(|n| format!("{},{}\n", n, n % 7)).collect::<String>();
+        let sequential = read_data_file(&mut ::std::io::Cursor::new(text.clone()), None);
+        for threads in [1, 3, 8, 2000] {
+            let parallel = read_data_file_parallel(&mut ::std::io::Cursor::new(text.clone()), threads);
+            assert_eq!(parallel, sequential);
+        }
+        assert!(read_data_file_parallel(&mut ::std::io::Cursor::new(""), 4).is_empty());
+    }
+
+    #[test]
+    fn test_write_sparklines() {
+        let data = (0..300).map(|n| vec![(n as f64 / 10.0).sin(), n as f64]).collect::<Vec<_>>();
+        let coord = ForwardDelayCoordinates {
+            delay: 2,
+            dimension: 3,
+        };
+        let mut output = Vec::new();
+        write_sparklines(&mut output, &coord, &data).unwrap();
+        let text = String::from_utf8(output).unwrap();
+        let lines = text.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 6);
+        assert!(lines.iter().all(|line| line.split(' ').nth(1).unwrap().chars().count() <= 80));
+        // The second channel is a ramp, so its sparklines rise from the lowest to the highest bar
+        let ramp = lines.iter().find(|line| line.starts_with(&header(&coord, 2)[1])).unwrap();
+        assert!(ramp.split(' ').nth(1).unwrap().starts_with('▁') && ramp.ends_with('█'));
+    }
+
+    #[test]
+    fn test_write_tisean() {
+        let data = [1.0, -0.5, 0.00125, 250.0, 3.0].iter().map(|&x| vec![x, 2.0 * x]).collect::<Vec<_>>();
+        let coord = ForwardDelayCoordinates {
+            delay: 2,
+            dimension: 2,
+        };
+        let mut output = Vec::new();
+        write_tisean(&mut output, &coord, &data).unwrap();
+        let expected = "\
+1.000000e+00 1.250000e-03 2.000000e+00 2.500000e-03
+-5.000000e-01 2.500000e+02 -1.000000e+00 5.000000e+02
+1.250000e-03 3.000000e+00 2.500000e-03 6.000000e+00
+";
+        assert_eq!(String::from_utf8(output).unwrap(), expected);
     }
 }
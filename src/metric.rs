@@ -0,0 +1,40 @@
+//! Distances between embedded vectors
+
+/// Metric on the delay-coordinate space
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Metric {
+    /// Euclidean distance
+    Euclidean,
+    /// Manhattan (taxicab) distance
+    Manhattan,
+    /// Maximum (Chebyshev) distance
+    Maximum,
+}
+
+impl Metric {
+    /// Calculates the distance between two vectors
+    ///
+    /// The vectors are compared coordinate-wise up to the length of the shorter one.
+    pub fn distance(&self, a: &[f64], b: &[f64]) -> f64 {
+        let diffs = a.iter().zip(b.iter()).map(|(x, y)| (x - y).abs());
+        match self {
+            Metric::Euclidean => diffs.map(|d| d * d).sum::<f64>().sqrt(),
+            Metric::Manhattan => diffs.sum(),
+            Metric::Maximum => diffs.fold(0.0, f64::max),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::metric::Metric;
+
+    #[test]
+    fn test_metric_distance() {
+        let a = [0.0, 0.0];
+        let b = [3.0, -4.0];
+        assert_eq!(Metric::Euclidean.distance(&a, &b), 5.0);
+        assert_eq!(Metric::Manhattan.distance(&a, &b), 7.0);
+        assert_eq!(Metric::Maximum.distance(&a, &b), 4.0);
+    }
+}
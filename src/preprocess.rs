@@ -0,0 +1,255 @@
+//! Preprocessing of series before embedding
+
+/// Discretizes a series into equal-width bins
+///
+/// The range between the minimum and the maximum of `series` is divided into `bins` bins and
+/// each sample is replaced with the index of its bin. A constant series falls into bin 0.
+pub fn discretize(series: &[f64], bins: usize) -> Vec<usize> {
+    let min = series.iter().cloned().fold(f64::INFINITY, f64::min);
+    let max = series.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+    let width = (max - min) / bins as f64;
+    series.iter()
+        .map(|&x| {
+            if width > 0.0 {
+                (((x - min) / width) as usize).min(bins-1)
+            } else {
+                0
+            }
+        })
+        .collect()
+}
+
+/// Returns `true` if the series is empty or constant
+///
+/// Every embedding of such a series collapses to a single point and its distances are zero.
+pub fn is_degenerate(series: &[f64]) -> bool {
+    match series.first() {
+        Some(&first) => series.iter().all(|&x| x == first),
+        None => true,
+    }
+}
+
+/// Running mean and variance
+///
+/// This struct accumulates the mean and the variance of pushed samples with Welford's algorithm,
+/// so the statistics are available at any time without re-scanning the data.
+#[derive(Debug, Clone, Default)]
+pub struct RunningStats {
+    count: usize,
+    mean: f64,
+    m2: f64,
+}
+
+impl RunningStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a sample to the statistics
+    pub fn push(&mut self, value: f64) {
+        self.count += 1;
+        let delta = value - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (value - self.mean);
+    }
+
+    /// Number of pushed samples
+    #[inline]
+    pub fn count(&self) -> usize { self.count }
+
+    /// Mean of the pushed samples
+    #[inline]
+    pub fn mean(&self) -> f64 { self.mean }
+
+    /// Population variance of the pushed samples
+    #[inline]
+    pub fn variance(&self) -> f64 {
+        if self.count == 0 {
+            0.0
+        } else {
+            self.m2 / self.count as f64
+        }
+    }
+
+    /// Population standard deviation of the pushed samples
+    #[inline]
+    pub fn std_dev(&self) -> f64 {
+        self.variance().sqrt()
+    }
+}
+
+/// Streaming z-score normalization
+///
+/// Each pushed sample is normalized by the running statistics of the samples pushed so far,
+/// including the sample itself.
+/// While the standard deviation is zero, the normalized value is zero.
+#[derive(Debug, Clone, Default)]
+pub struct StreamingZScore {
+    stats: RunningStats,
+}
+
+impl StreamingZScore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a sample and returns its normalized value
+    pub fn push(&mut self, value: f64) -> f64 {
+        self.stats.push(value);
+        let std_dev = self.stats.std_dev();
+        if std_dev > 0.0 {
+            (value - self.stats.mean()) / std_dev
+        } else {
+            0.0
+        }
+    }
+
+    /// Running statistics of the pushed samples
+    #[inline]
+    pub fn stats(&self) -> &RunningStats { &self.stats }
+}
+
+/// Per-coordinate standardization of embedded vectors
+///
+/// The mean and the population standard deviation of each coordinate are fitted on training
+/// vectors, and the same scaling is then applied to any vector, so training and test vectors
+/// are scaled consistently. A coordinate with zero standard deviation is scaled to zero.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StandardScaler {
+    mean: Vec<f64>,
+    std_dev: Vec<f64>,
+}
+
+impl StandardScaler {
+    /// Fits the scaler on the vectors, e.g. `coord.mapping_iter(train).to_vec()`
+    ///
+    /// The dimension is taken from the first vector, and the scaler of no vectors has dimension 0.
+    pub fn fit<I, V>(vectors: I) -> Self
+    where
+        I: IntoIterator<Item = V>,
+        V: AsRef<[f64]>,
+    {
+        let mut stats: Vec<RunningStats> = Vec::new();
+        for v in vectors {
+            let v = v.as_ref();
+            if stats.is_empty() {
+                stats = vec![RunningStats::new(); v.len()];
+            }
+            for (s, &x) in stats.iter_mut().zip(v.iter()) {
+                s.push(x);
+            }
+        }
+        StandardScaler {
+            mean: stats.iter().map(RunningStats::mean).collect(),
+            std_dev: stats.iter().map(RunningStats::std_dev).collect(),
+        }
+    }
+
+    /// Fits the scaler on the vectors and returns it with the scaled vectors
+    pub fn fit_transform<I, V>(vectors: I) -> (Self, Vec<Vec<f64>>)
+    where
+        I: IntoIterator<Item = V>,
+        V: AsRef<[f64]>,
+    {
+        let vectors = vectors.into_iter().collect::<Vec<V>>();
+        let scaler = Self::fit(vectors.iter().map(|v| v.as_ref()));
+        let scaled = scaler.transform_iter(vectors.iter().map(|v| v.as_ref())).collect();
+        (scaler, scaled)
+    }
+
+    /// Scales a vector
+    ///
+    /// # Panics
+    ///
+    /// Panics if the length of `vector` is not the fitted dimension.
+    pub fn transform(&self, vector: &[f64]) -> Vec<f64> {
+        assert_eq!(vector.len(), self.mean.len(), "vector must have the fitted dimension");
+        vector.iter()
+            .zip(self.mean.iter().zip(self.std_dev.iter()))
+            .map(|(x, (mean, std_dev))| if *std_dev > 0.0 { (x - mean) / std_dev } else { 0.0 })
+            .collect()
+    }
+
+    /// Scales each vector of an iterator lazily
+    pub fn transform_iter<'a, I, V>(&'a self, vectors: I) -> impl Iterator<Item = Vec<f64>> + 'a
+    where
+        I: IntoIterator<Item = V>,
+        I::IntoIter: 'a,
+        V: AsRef<[f64]>,
+    {
+        vectors.into_iter().map(move |v| self.transform(v.as_ref()))
+    }
+
+    /// Fitted means of the coordinates
+    #[inline]
+    pub fn mean(&self) -> &[f64] { &self.mean }
+
+    /// Fitted standard deviations of the coordinates
+    #[inline]
+    pub fn std_dev(&self) -> &[f64] { &self.std_dev }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{DelayCoordinates, ForwardDelayCoordinates};
+    use crate::preprocess::{RunningStats, StandardScaler, StreamingZScore, discretize, is_degenerate};
+
+    #[test]
+    fn test_discretize() {
+        assert_eq!(discretize(&[0.0, 0.5, 1.0, 2.0, 4.0], 4), vec![0, 0, 1, 2, 3]);
+        assert_eq!(discretize(&[1.0, 1.0], 4), vec![0, 0]);
+    }
+
+    #[test]
+    fn test_is_degenerate() {
+        assert!(is_degenerate(&[]));
+        assert!(is_degenerate(&[2.5, 2.5, 2.5]));
+        assert!(!is_degenerate(&[2.5, 2.5, 2.0]));
+    }
+
+    #[test]
+    fn test_running_stats() {
+        let data = (0..100).map(|n| ((n * 37) % 11) as f64 * 0.5 - 1.0).collect::<Vec<f64>>();
+        let mut stats = RunningStats::new();
+        for &x in data.iter() {
+            stats.push(x);
+        }
+        let n = data.len() as f64;
+        let mean = data.iter().sum::<f64>() / n;
+        let variance = data.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / n;
+        assert_eq!(stats.count(), data.len());
+        assert!((stats.mean() - mean).abs() < 1e-12);
+        assert!((stats.variance() - variance).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_streaming_zscore() {
+        let mut zscore = StreamingZScore::new();
+        assert_eq!(zscore.push(3.0), 0.0);
+        assert_eq!(zscore.push(5.0), 1.0);
+        assert_eq!(zscore.stats().mean(), 4.0);
+    }
+
+    #[test]
+    fn test_standard_scaler() {
+        let data = (0..50).map(|n| ((n * 7) % 13) as f64 + 0.1 * n as f64).collect::<Vec<f64>>();
+        let coord = ForwardDelayCoordinates {
+            delay: 2,
+            dimension: 3,
+        };
+        let (train, test) = data.split_at(30);
+        let (scaler, scaled) = StandardScaler::fit_transform(coord.mapping_iter(train).to_vec());
+        assert_eq!(scaled.len(), 26);
+        for k in 0..3 {
+            let mut stats = RunningStats::new();
+            scaled.iter().for_each(|v| stats.push(v[k]));
+            assert!(stats.mean().abs() < 1e-12);
+            assert!((stats.variance() - 1.0).abs() < 1e-12);
+        }
+        let scaled_test = scaler.transform_iter(coord.mapping_iter(test).to_vec()).collect::<Vec<_>>();
+        assert_eq!(scaled_test.len(), 16);
+        let first = coord.mapping_iter(test).next().unwrap().to_vec();
+        assert_eq!(scaled_test[0], scaler.transform(&first));
+        assert_eq!(StandardScaler::fit(vec![vec![1.0, 2.0]; 3]).transform(&[1.0, 5.0]), vec![0.0, 0.0]);
+    }
+}
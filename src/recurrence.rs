@@ -0,0 +1,705 @@
+//! Recurrence plots and recurrence quantification analysis
+//!
+//! Two embedded points $i$ and $j$ are recurrent when their distance is at most `threshold`.
+
+use crate::{DelayCoordinates, ForwardDelayCoordinates};
+use crate::analysis::{DistanceCache, NeighborSearch};
+use crate::metric::Metric;
+
+/// Computes the recurrence matrix of the embedded series
+pub fn recurrence_matrix<C>(coord: &C, slice: &[f64], threshold: f64, metric: Metric) -> Vec<Vec<bool>>
+where
+    C: DelayCoordinates,
+{
+    let points = crate::embed(coord, slice);
+    points.iter()
+        .map(|p| points.iter().map(|q| metric.distance(p, q) <= threshold).collect())
+        .collect()
+}
+
+/// Computes the cross-recurrence matrix of two series embedded in the same delay-coordinates
+///
+/// The element `[i][j]` is `true` if the `i`-th embedded point of `x` and the `j`-th embedded
+/// point of `y` are at most `threshold` apart. The matrix is rectangular if the series have
+/// different lengths, and a lag between the series shifts its main diagonal.
+pub fn cross_recurrence_matrix<C>(coord: &C, x: &[f64], y: &[f64], threshold: f64, metric: Metric) -> Vec<Vec<bool>>
+where
+    C: DelayCoordinates,
+{
+    let (px, py) = (crate::embed(coord, x), crate::embed(coord, y));
+    px.iter()
+        .map(|p| py.iter().map(|q| metric.distance(p, q) <= threshold).collect())
+        .collect()
+}
+
+/// Computes the joint recurrence matrix of several channels
+///
+/// Each channel is a pair of delay-coordinates and a series, and the element `[i][j]` is `true`
+/// if the `i`-th and the `j`-th points are recurrent in every channel, i.e. the recurrence
+/// matrices are combined by the elementwise AND. The points of the channels are aligned at
+/// their first windows and truncated to the channel with the fewest points.
+pub fn joint_recurrence_matrix<C>(channels: &[(&C, &[f64])], threshold: f64, metric: Metric) -> Vec<Vec<bool>>
+where
+    C: DelayCoordinates,
+{
+    let matrices = channels.iter()
+        .map(|&(coord, series)| recurrence_matrix(coord, series, threshold, metric))
+        .collect::<Vec<_>>();
+    let n = matrices.iter().map(|matrix| matrix.len()).min().unwrap_or(0);
+    (0..n)
+        .map(|i| (0..n).map(|j| matrices.iter().all(|matrix| matrix[i][j])).collect())
+        .collect()
+}
+
+/// Computes the recurrence matrix from precomputed distances
+pub fn recurrence_matrix_cached(cache: &DistanceCache, threshold: f64) -> Vec<Vec<bool>> {
+    (0..cache.len())
+        .map(|i| (0..cache.len()).map(|j| cache.get(i, j) <= threshold).collect())
+        .collect()
+}
+
+/// Computes the recurrence matrix with a fixed number of neighbors
+///
+/// The element `[i][j]` is `true` if the `j`-th point is one of the `k` nearest neighbors of the
+/// `i`-th point outside the Theiler window, so every row has `k` recurrences unless there are
+/// fewer admissible neighbors. The matrix is not symmetric in general and its diagonal is
+/// `false`. Ties are resolved in favor of the smallest index.
+pub fn knn_recurrence<C>(coord: &C, slice: &[f64], k: usize, metric: Metric, theiler: usize) -> Vec<Vec<bool>>
+where
+    C: DelayCoordinates,
+{
+    let points = crate::embed(coord, slice);
+    let search = NeighborSearch {
+        theiler,
+        ..NeighborSearch::new(metric)
+    };
+    (0..points.len())
+        .map(|i| {
+            let mut row = vec![false; points.len()];
+            for j in search.k_nearest(&points, i, k) {
+                row[j] = true;
+            }
+            row
+        })
+        .collect()
+}
+
+/// Computes the recurrence rate, the fraction of recurrent pairs outside the line of identity
+pub fn recurrence_rate<C>(coord: &C, slice: &[f64], threshold: f64, metric: Metric) -> f64
+where
+    C: DelayCoordinates,
+{
+    let matrix = recurrence_matrix(coord, slice, threshold, metric);
+    let n = matrix.len();
+    if n < 2 {
+        return 0.0;
+    }
+    let recurrent = matrix.iter()
+        .enumerate()
+        .map(|(i, row)| row.iter().enumerate().filter(|&(j, &r)| i != j && r).count())
+        .sum::<usize>();
+    recurrent as f64 / (n * (n-1)) as f64
+}
+
+/// Computes the recurrence rate for the embedding dimensions `1..=max_dim`
+///
+/// The series is embedded with forward delay-coordinates with the given delay.
+/// The recurrence rate stops changing once the dimension is large enough to unfold the attractor.
+pub fn rate_vs_dimension(series: &[f64], delay: usize, max_dim: usize, threshold: f64, metric: Metric) -> Vec<f64> {
+    (1..=max_dim)
+        .map(|dimension| {
+            let coord = ForwardDelayCoordinates {
+                delay,
+                dimension,
+            };
+            recurrence_rate(&coord, series, threshold, metric)
+        })
+        .collect()
+}
+
+/// Computes the histogram of diagonal line lengths of the recurrence matrix
+///
+/// The `l`-th element of the returned vector is the number of diagonal lines of length exactly `l`.
+/// The line of identity is excluded and both triangles of the matrix are counted.
+pub fn diagonal_line_histogram<C>(coord: &C, slice: &[f64], threshold: f64, metric: Metric) -> Vec<usize>
+where
+    C: DelayCoordinates,
+{
+    diagonal_lines(&recurrence_matrix(coord, slice, threshold, metric))
+}
+
+/// Computes the histogram of vertical line lengths of the recurrence matrix
+///
+/// The `v`-th element of the returned vector is the number of vertical lines of length exactly `v`.
+/// The line of identity is excluded, so it splits the vertical lines crossing it.
+pub fn vertical_line_histogram<C>(coord: &C, slice: &[f64], threshold: f64, metric: Metric) -> Vec<usize>
+where
+    C: DelayCoordinates,
+{
+    vertical_lines(&recurrence_matrix(coord, slice, threshold, metric))
+}
+
+fn diagonal_lines(matrix: &[Vec<bool>]) -> Vec<usize> {
+    let n = matrix.len();
+    let mut hist = vec![0; n+1];
+    for offset in 1..n {
+        let upper = (0..n-offset).map(|i| matrix[i][i+offset]);
+        let lower = (0..n-offset).map(|i| matrix[i+offset][i]);
+        count_lines(upper, &mut hist);
+        count_lines(lower, &mut hist);
+    }
+    hist
+}
+
+fn vertical_lines(matrix: &[Vec<bool>]) -> Vec<usize> {
+    let n = matrix.len();
+    let mut hist = vec![0; n+1];
+    for j in 0..n {
+        count_lines(matrix.iter().enumerate().map(|(i, row)| i != j && row[j]), &mut hist);
+    }
+    hist
+}
+
+/// Histograms of the recurrence times of the first and the second type
+///
+/// The `t`-th element of each histogram is the number of recurrence times equal to `t`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecurrenceTimes {
+    /// Times between successive later points recurrent to each point
+    pub first: Vec<usize>,
+    /// Times between the starts of successive runs of later points recurrent to each point
+    pub second: Vec<usize>,
+}
+
+/// Computes the histograms of recurrence times of the embedded series
+///
+/// For each point, the later points recurrent to it form runs in time. The recurrence times of
+/// the first type are the times between all of them, starting from the point itself, so they
+/// count the points of a trajectory staying near the state as times of 1. The recurrence times
+/// of the second type are the times between the starts of successive runs, so they do not
+/// count those points, and a periodic series peaks at its period.
+pub fn recurrence_times<C>(coord: &C, slice: &[f64], threshold: f64, metric: Metric) -> RecurrenceTimes
+where
+    C: DelayCoordinates,
+{
+    let matrix = recurrence_matrix(coord, slice, threshold, metric);
+    let n = matrix.len();
+    let mut first = vec![0; n];
+    let mut second = vec![0; n];
+    for (i, row) in matrix.iter().enumerate() {
+        let (mut last_point, mut last_run) = (i, i);
+        for j in i+1..n {
+            if row[j] {
+                first[j-last_point] += 1;
+                last_point = j;
+                if !row[j-1] {
+                    second[j-last_run] += 1;
+                    last_run = j;
+                }
+            }
+        }
+    }
+    RecurrenceTimes {
+        first,
+        second,
+    }
+}
+
+/// Computes the fraction of recurrences lying on the diagonals of the dominant period
+///
+/// The recurrences are counted on each diagonal $j - i = k > 0$.
+/// The diagonals next to the main one hold the recurrences of the trajectory staying near itself.
+/// They are left out up to the first local minimum of the counts, a band of width $w$.
+/// The dominant period $P$ starts from the fullest diagonal beyond the band.
+/// It is divided by the largest $m$ such that the diagonals near each of its $m$-th parts hold
+/// at least half as many recurrences, so that a multiple of the period is not taken.
+/// The family of $P$ is the diagonals within $w$ of each multiple of $P$.
+/// At each multiple, $P$ is refined by the centroid of the counts, so that a period between two
+/// integers is followed.
+/// The result is the fraction of the remaining recurrences on the family.
+/// It is close to 1 for a periodic series and small for an aperiodic one.
+/// It is 0 if there is no recurrence beyond the band.
+pub fn diagonal_dominance<C>(coord: &C, slice: &[f64], threshold: f64, metric: Metric) -> f64
+where
+    C: DelayCoordinates,
+{
+    let matrix = recurrence_matrix(coord, slice, threshold, metric);
+    let n = matrix.len();
+    let counts = (0..n)
+        .map(|k| (0..n-k).filter(|&i| matrix[i][i+k]).count())
+        .collect::<Vec<usize>>();
+    let width = match (1..n.saturating_sub(1)).find(|&k| counts[k] <= counts[k+1]) {
+        Some(width) => width,
+        None => return 0.0,
+    };
+    let total = counts[width+1..].iter().sum::<usize>();
+    if total == 0 {
+        return 0.0;
+    }
+    let fullest = (width+1..n).max_by(|&a, &b| counts[a].cmp(&counts[b]).then(b.cmp(&a))).unwrap();
+    let divisor = (1..=fullest / (width + 1))
+        .rev()
+        .find(|&m| {
+            (1..m).all(|j| {
+                let center = (fullest as f64 * j as f64 / m as f64).round() as usize;
+                let around = (center - width).max(width + 1)..=center + width;
+                2 * counts[around].iter().cloned().max().unwrap_or(0) >= counts[fullest]
+            })
+        })
+        .unwrap_or(1);
+    let mut period = fullest as f64 / divisor as f64;
+    let mut dominant = 0;
+    let mut next = width + 1;
+    for multiple in 1.. {
+        let center = multiple as f64 * period;
+        let start = ((center - width as f64).ceil() as usize).max(next);
+        if start >= n {
+            break;
+        }
+        let end = ((center + width as f64).floor() as usize).min(n - 1);
+        let family = start..(end + 1).max(start);
+        let count = counts[family.clone()].iter().sum::<usize>();
+        if count > 0 {
+            let centroid = counts[family.clone()].iter().zip(family).map(|(&c, k)| (c * k) as f64).sum::<f64>()
+                / count as f64;
+            period = centroid / multiple as f64;
+        }
+        dominant += count;
+        next = next.max(end + 1);
+    }
+    dominant as f64 / total as f64
+}
+
+/// Shannon entropy of the distribution of diagonal line lengths (ENTR)
+///
+/// Only lines of length at least `l_min` are taken into account.
+pub fn entropy_of_diagonal_lines(hist: &[usize], l_min: usize) -> f64 {
+    let total = hist.iter().skip(l_min).sum::<usize>();
+    if total == 0 {
+        return 0.0;
+    }
+    hist.iter()
+        .skip(l_min)
+        .filter(|&&count| count > 0)
+        .map(|&count| {
+            let p = count as f64 / total as f64;
+            -p * p.ln()
+        })
+        .sum()
+}
+
+/// Average length of vertical lines (TT, trapping time)
+///
+/// Only lines of length at least `v_min` are taken into account.
+pub fn trapping_time(hist: &[usize], v_min: usize) -> f64 {
+    let count = hist.iter().skip(v_min).sum::<usize>();
+    if count == 0 {
+        return 0.0;
+    }
+    weighted_sum(hist, v_min) as f64 / count as f64
+}
+
+/// Sums `l * hist[l]` over `l >= min`
+fn weighted_sum(hist: &[usize], min: usize) -> usize {
+    hist.iter().enumerate().skip(min).map(|(l, count)| l * count).sum()
+}
+
+/// Result of recurrence quantification analysis
+#[derive(Debug, Clone, PartialEq)]
+pub struct RqaResult {
+    /// Fraction of recurrent pairs outside the line of identity (RR)
+    pub recurrence_rate: f64,
+    /// Fraction of recurrent points forming diagonal lines (DET)
+    pub determinism: f64,
+    /// Fraction of recurrent points forming vertical lines (LAM)
+    pub laminarity: f64,
+    /// Entropy of diagonal line lengths (ENTR)
+    pub entropy: f64,
+    /// Average length of vertical lines (TT)
+    pub trapping_time: f64,
+}
+
+/// Performs recurrence quantification analysis
+///
+/// Diagonal and vertical lines shorter than `l_min` are not counted as lines.
+pub fn rqa<C>(coord: &C, slice: &[f64], threshold: f64, metric: Metric, l_min: usize) -> RqaResult
+where
+    C: DelayCoordinates,
+{
+    rqa_of_matrix(&recurrence_matrix(coord, slice, threshold, metric), l_min)
+}
+
+/// Performs recurrence quantification analysis in sliding windows of the series
+///
+/// The $k$-th element is the result of `rqa` on `series[k*step..k*step+window]`, so the
+/// result is a time series of the measures that reveals changes of the dynamics.
+///
+/// # Panics
+///
+/// Panics if `step` is zero.
+pub fn rolling_rqa<C>(series: &[f64], coord: &C, window: usize, step: usize, threshold: f64, metric: Metric,
+                      l_min: usize) -> Vec<RqaResult>
+where
+    C: DelayCoordinates,
+{
+    assert!(step > 0, "step must be positive");
+    (0..(series.len()+1).saturating_sub(window))
+        .step_by(step)
+        .map(|start| rqa(coord, &series[start..start+window], threshold, metric, l_min))
+        .collect()
+}
+
+/// Performs recurrence quantification analysis from precomputed distances
+pub fn rqa_cached(cache: &DistanceCache, threshold: f64, l_min: usize) -> RqaResult {
+    rqa_of_matrix(&recurrence_matrix_cached(cache, threshold), l_min)
+}
+
+fn rqa_of_matrix(matrix: &[Vec<bool>], l_min: usize) -> RqaResult {
+    let n = matrix.len();
+    let diagonal = diagonal_lines(matrix);
+    let vertical = vertical_lines(matrix);
+    let recurrent = weighted_sum(&diagonal, 1);
+    let ratio = |num: usize| if recurrent == 0 { 0.0 } else { num as f64 / recurrent as f64 };
+    RqaResult {
+        recurrence_rate: if n < 2 { 0.0 } else { recurrent as f64 / (n * (n-1)) as f64 },
+        determinism: ratio(weighted_sum(&diagonal, l_min)),
+        laminarity: ratio(weighted_sum(&vertical, l_min)),
+        entropy: entropy_of_diagonal_lines(&diagonal, l_min),
+        trapping_time: trapping_time(&vertical, l_min),
+    }
+}
+
+/// Builds the recurrence network as adjacency lists
+///
+/// The embedded points are the nodes and recurrent pairs are the edges.
+/// Self-loops are excluded and the neighbors of each node are sorted.
+pub fn recurrence_network<C>(coord: &C, slice: &[f64], threshold: f64, metric: Metric) -> Vec<Vec<usize>>
+where
+    C: DelayCoordinates,
+{
+    recurrence_matrix(coord, slice, threshold, metric)
+        .iter()
+        .enumerate()
+        .map(|(i, row)| (0..row.len()).filter(|&j| j != i && row[j]).collect())
+        .collect()
+}
+
+/// Computes the size of the largest connected component of the recurrence network
+///
+/// A fragmented network suggests a poor embedding or a too sparse sampling of the attractor.
+pub fn largest_component<C>(coord: &C, slice: &[f64], threshold: f64, metric: Metric) -> usize
+where
+    C: DelayCoordinates,
+{
+    let adjacency = recurrence_network(coord, slice, threshold, metric);
+    let mut visited = vec![false; adjacency.len()];
+    let mut largest = 0;
+    for root in 0..adjacency.len() {
+        if visited[root] {
+            continue;
+        }
+        visited[root] = true;
+        let mut stack = vec![root];
+        let mut size = 0;
+        while let Some(node) = stack.pop() {
+            size += 1;
+            for &next in adjacency[node].iter() {
+                if !visited[next] {
+                    visited[next] = true;
+                    stack.push(next);
+                }
+            }
+        }
+        largest = largest.max(size);
+    }
+    largest
+}
+
+/// Degrees of the nodes of a network given as adjacency lists
+pub fn degrees(adjacency: &[Vec<usize>]) -> Vec<usize> {
+    adjacency.iter().map(|neighbors| neighbors.len()).collect()
+}
+
+/// Local clustering coefficients of the nodes of a network given as adjacency lists
+///
+/// The coefficient of a node with less than two neighbors is zero.
+pub fn clustering_coefficients(adjacency: &[Vec<usize>]) -> Vec<f64> {
+    adjacency.iter()
+        .map(|neighbors| {
+            let k = neighbors.len();
+            if k < 2 {
+                return 0.0;
+            }
+            let links = neighbors.iter()
+                .map(|&u| neighbors.iter().filter(|&&v| adjacency[u].binary_search(&v).is_ok()).count())
+                .sum::<usize>();
+            links as f64 / (k * (k-1)) as f64
+        })
+        .collect()
+}
+
+/// Adds the lengths of runs of `true` in `line` to `hist`
+fn count_lines<I: Iterator<Item = bool>>(line: I, hist: &mut [usize]) {
+    let mut length = 0;
+    for r in line {
+        if r {
+            length += 1;
+        } else if length > 0 {
+            hist[length] += 1;
+            length = 0;
+        }
+    }
+    if length > 0 {
+        hist[length] += 1;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::ForwardDelayCoordinates;
+    use crate::metric::Metric;
+    use crate::recurrence::{diagonal_line_histogram, recurrence_matrix, rqa, vertical_line_histogram};
+    use crate::recurrence::{clustering_coefficients, degrees, largest_component, recurrence_network};
+    use crate::recurrence::{knn_recurrence, rate_vs_dimension, recurrence_rate, recurrence_times};
+    use crate::recurrence::{recurrence_matrix_cached, rolling_rqa, rqa_cached};
+    use crate::recurrence::{cross_recurrence_matrix, diagonal_dominance, joint_recurrence_matrix};
+    use crate::analysis::DistanceCache;
+    use crate::testutil::{sine, white_noise};
+
+    #[test]
+    fn test_recurrence_matrix() {
+        let data = vec![0.0, 1.0, 0.0, 1.0];
+        let coord = ForwardDelayCoordinates {
+            delay: 1,
+            dimension: 1,
+        };
+        let matrix = recurrence_matrix(&coord, &data, 0.5, Metric::Euclidean);
+        assert_eq!(matrix[0], vec![true, false, true, false]);
+        assert_eq!(matrix[1], vec![false, true, false, true]);
+    }
+
+    #[test]
+    fn test_diagonal_line_histogram() {
+        let data = (0..20).map(|n| (n % 4) as f64).collect::<Vec<f64>>();
+        let coord = ForwardDelayCoordinates {
+            delay: 1,
+            dimension: 2,
+        };
+        let hist = diagonal_line_histogram(&coord, &data, 0.5, Metric::Euclidean);
+        assert_eq!(hist.len(), 20);
+        for (length, &count) in hist.iter().enumerate() {
+            let expected = if [3, 7, 11, 15].contains(&length) { 2 } else { 0 };
+            assert_eq!(count, expected);
+        }
+    }
+
+    #[test]
+    fn test_vertical_line_histogram() {
+        let data = vec![0.0, 0.0, 0.0, 1.0];
+        let coord = ForwardDelayCoordinates {
+            delay: 1,
+            dimension: 1,
+        };
+        let hist = vertical_line_histogram(&coord, &data, 0.5, Metric::Euclidean);
+        assert_eq!(hist, vec![0, 2, 2, 0, 0]);
+    }
+
+    #[test]
+    fn test_rqa_periodic_and_stochastic() {
+        let coord = ForwardDelayCoordinates {
+            delay: 2,
+            dimension: 3,
+        };
+        let periodic = rqa(&coord, &sine(300, 20.0), 0.1, Metric::Maximum, 2);
+        let stochastic = rqa(&coord, &white_noise(300, 1), 0.1, Metric::Maximum, 2);
+        assert!(periodic.determinism > 0.95);
+        assert!(periodic.determinism > stochastic.determinism);
+        assert!(periodic.entropy > stochastic.entropy);
+
+        let held = white_noise(60, 2).iter()
+            .flat_map(|&x| std::iter::repeat_n(x, 5))
+            .collect::<Vec<f64>>();
+        let laminar = rqa(&coord, &held, 0.1, Metric::Maximum, 2);
+        assert!(laminar.laminarity > stochastic.laminarity);
+        assert!(laminar.trapping_time > stochastic.trapping_time);
+    }
+
+    #[test]
+    fn test_recurrence_network() {
+        let data = vec![0.0, 0.1, 1.0, 0.05, 2.0];
+        let coord = ForwardDelayCoordinates {
+            delay: 1,
+            dimension: 1,
+        };
+        let adjacency = recurrence_network(&coord, &data, 0.2, Metric::Euclidean);
+        assert_eq!(adjacency, vec![vec![1, 3], vec![0, 3], vec![], vec![0, 1], vec![]]);
+        let matrix = recurrence_matrix(&coord, &data, 0.2, Metric::Euclidean);
+        for (i, (neighbors, row)) in adjacency.iter().zip(matrix.iter()).enumerate() {
+            for (j, &r) in row.iter().enumerate() {
+                assert_eq!(neighbors.contains(&j), i != j && r);
+            }
+        }
+        assert_eq!(degrees(&adjacency), vec![2, 2, 0, 2, 0]);
+        assert_eq!(clustering_coefficients(&adjacency), vec![1.0, 1.0, 0.0, 1.0, 0.0]);
+    }
+
+    #[test]
+    fn test_recurrence_rate() {
+        let data = vec![0.0, 1.0, 0.0, 1.0];
+        let coord = ForwardDelayCoordinates {
+            delay: 1,
+            dimension: 1,
+        };
+        assert_eq!(recurrence_rate(&coord, &data, 0.5, Metric::Euclidean), 1.0 / 3.0);
+        assert_eq!(recurrence_rate(&coord, &data, -1.0, Metric::Euclidean), 0.0);
+        assert_eq!(recurrence_rate(&coord, &[0.0, f64::NAN, 0.0, 1.0], 0.5, Metric::Euclidean), 1.0 / 6.0);
+        assert_eq!(rate_vs_dimension(&[0.0, f64::NAN, 0.0, 1.0], 1, 2, -1.0, Metric::Euclidean), vec![0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_rate_vs_dimension() {
+        let series = sine(300, 20.0);
+        let rates = rate_vs_dimension(&series, 5, 5, 0.2, Metric::Maximum);
+        assert_eq!(rates.len(), 5);
+        assert!(rates[1] < 0.8 * rates[0]);
+        for m in 2..5 {
+            assert!((rates[m] - rates[1]).abs() < 0.1 * rates[1]);
+        }
+    }
+
+    #[test]
+    fn test_rqa_cached() {
+        let data = sine(150, 17.0);
+        let coord = ForwardDelayCoordinates {
+            delay: 4,
+            dimension: 3,
+        };
+        let cache = DistanceCache::new(&coord, &data, Metric::Maximum);
+        assert_eq!(recurrence_matrix_cached(&cache, 0.2),
+                   recurrence_matrix(&coord, &data, 0.2, Metric::Maximum));
+        assert_eq!(rqa_cached(&cache, 0.2, 2), rqa(&coord, &data, 0.2, Metric::Maximum, 2));
+    }
+
+    #[test]
+    fn test_rolling_rqa_regime_change() {
+        // A periodic half followed by the chaotic logistic map scaled to [-1, 1]
+        let mut series = sine(300, 20.0);
+        let mut x = 0.3f64;
+        for _ in 0..300 {
+            x = 4.0 * x * (1.0 - x);
+            series.push(2.0 * x - 1.0);
+        }
+        let coord = ForwardDelayCoordinates {
+            delay: 1,
+            dimension: 3,
+        };
+        let results = rolling_rqa(&series, &coord, 150, 75, 0.1, Metric::Maximum, 2);
+        assert_eq!(results.len(), 7);
+        assert!(results[0].determinism > 0.95);
+        assert!(results[6].determinism < results[0].determinism - 0.2);
+    }
+
+    #[test]
+    fn test_largest_component() {
+        let coord = ForwardDelayCoordinates {
+            delay: 5,
+            dimension: 2,
+        };
+        let periodic = sine(200, 20.0);
+        assert_eq!(largest_component(&coord, &periodic, 0.35, Metric::Euclidean), 195);
+        let sparse = white_noise(200, 8);
+        let fragmented = largest_component(&coord, &sparse, 0.05, Metric::Euclidean);
+        assert!(fragmented < 20);
+        assert_eq!(largest_component(&coord, &sparse[..4], 0.05, Metric::Euclidean), 0);
+    }
+
+    #[test]
+    fn test_recurrence_times() {
+        let coord = ForwardDelayCoordinates {
+            delay: 5,
+            dimension: 2,
+        };
+        let dominant = |hist: &[usize]| (0..hist.len()).max_by_key(|&t| hist[t]).unwrap();
+        // Successive points are about 0.31 apart, so the runs have a single point
+        let times = recurrence_times(&coord, &sine(300, 20.0), 0.2, Metric::Euclidean);
+        assert_eq!(dominant(&times.second), 20);
+        assert_eq!(times.second.iter().sum::<usize>(), times.second[20]);
+        assert_eq!(times.first, times.second);
+        // The runs have three points, which the first type counts as times of 1
+        let times = recurrence_times(&coord, &sine(300, 20.0), 0.5, Metric::Euclidean);
+        assert_eq!(dominant(&times.second), 20);
+        assert_eq!(dominant(&times.first), 1);
+        assert!(times.first[18] > 0 && times.second[1] == 0);
+        let empty = recurrence_times(&coord, &[0.0; 3], 0.2, Metric::Euclidean);
+        assert!(empty.first.is_empty() && empty.second.is_empty());
+    }
+
+    #[test]
+    fn test_knn_recurrence() {
+        let coord = ForwardDelayCoordinates {
+            delay: 1,
+            dimension: 2,
+        };
+        let matrix = knn_recurrence(&coord, &white_noise(100, 3), 5, Metric::Euclidean, 2);
+        assert_eq!(matrix.len(), 99);
+        for (i, row) in matrix.iter().enumerate() {
+            assert_eq!(row.iter().filter(|&&r| r).count(), 5);
+            assert!(!row[i] && !row[i.saturating_sub(2)..(i+3).min(99)].iter().any(|&r| r));
+        }
+        let short = knn_recurrence(&coord, &[0.0, 1.0, 2.0, 3.0], 5, Metric::Euclidean, 0);
+        assert!(short.iter().all(|row| row.iter().filter(|&&r| r).count() == 2));
+    }
+
+    #[test]
+    fn test_cross_recurrence_matrix() {
+        let coord = ForwardDelayCoordinates {
+            delay: 1,
+            dimension: 3,
+        };
+        let x = white_noise(60, 33);
+        let y = x[4..].to_vec();
+        let matrix = cross_recurrence_matrix(&coord, &x, &y, 1e-9, Metric::Euclidean);
+        assert_eq!((matrix.len(), matrix[0].len()), (58, 54));
+        for (i, row) in matrix.iter().enumerate() {
+            for (j, &r) in row.iter().enumerate() {
+                assert_eq!(r, i == j + 4);
+            }
+        }
+    }
+
+    #[test]
+    fn test_joint_recurrence_matrix() {
+        let coord = ForwardDelayCoordinates {
+            delay: 2,
+            dimension: 2,
+        };
+        let x = white_noise(80, 34);
+        let single = recurrence_matrix(&coord, &x, 0.3, Metric::Euclidean);
+        let joint = joint_recurrence_matrix(&[(&coord, &x[..]), (&coord, &x[..])], 0.3, Metric::Euclidean);
+        assert_eq!(joint, single);
+
+        let y = white_noise(70, 35);
+        let joint = joint_recurrence_matrix(&[(&coord, &x[..]), (&coord, &y[..])], 0.3, Metric::Euclidean);
+        let other = recurrence_matrix(&coord, &y, 0.3, Metric::Euclidean);
+        assert_eq!(joint.len(), 68);
+        for (i, row) in joint.iter().enumerate() {
+            for (j, &r) in row.iter().enumerate() {
+                assert_eq!(r, single[i][j] && other[i][j]);
+            }
+        }
+        assert!(joint_recurrence_matrix::<ForwardDelayCoordinates>(&[], 0.3, Metric::Euclidean).is_empty());
+    }
+
+    #[test]
+    fn test_diagonal_dominance() {
+        let coord = ForwardDelayCoordinates {
+            delay: 3,
+            dimension: 3,
+        };
+        let periodic = diagonal_dominance(&coord, &sine(600, 23.7), 0.3, Metric::Euclidean);
+        let aperiodic = diagonal_dominance(&coord, &white_noise(600, 21), 0.3, Metric::Euclidean);
+        assert!(periodic > 0.9);
+        assert!(aperiodic < 0.3);
+        assert_eq!(diagonal_dominance(&coord, &[1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0], 0.1, Metric::Euclidean), 0.0);
+    }
+}
@@ -0,0 +1,33 @@
+//! Small deterministic random number generator for reproducible randomized methods
+
+/// Xorshift64 generator
+#[derive(Debug, Clone)]
+pub(crate) struct Xorshift {
+    state: u64,
+}
+
+impl Xorshift {
+    /// Creates a generator whose sequence is fixed by `seed`
+    pub(crate) fn new(seed: u64) -> Self {
+        Xorshift {
+            state: seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1,
+        }
+    }
+
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state
+    }
+
+    /// Uniform sample on $[0, 1)$
+    pub(crate) fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Uniform sample on $\{0, \ldots, n-1\}$
+    pub(crate) fn below(&mut self, n: usize) -> usize {
+        ((self.next_f64() * n as f64) as usize).min(n.saturating_sub(1))
+    }
+}
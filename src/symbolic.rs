@@ -0,0 +1,137 @@
+//! Symbolic dynamics of embedded series
+//!
+//! The embedded vectors are mapped to symbols by their ordinal patterns, so the symbols do not
+//! depend on the amplitude of the series.
+
+use crate::DelayCoordinates;
+
+/// Maps each embedded vector to the index of its ordinal pattern
+///
+/// The ordinal pattern of a vector is the permutation sorting its coordinates in increasing
+/// order, where ties are broken by the coordinate index. The coordinates are compared by
+/// `f64::total_cmp`, so a NaN gets a deterministic rank, above every number unless its sign bit
+/// is set. The permutations of $d$ coordinates are numbered from 0 to $d! - 1$ by their Lehmer
+/// codes.
+pub fn ordinal_patterns<C>(coord: &C, slice: &[f64]) -> Vec<usize>
+where
+    C: DelayCoordinates,
+{
+    coord.mapping_iter(slice)
+        .map(|view| {
+            let v = view.to_vec();
+            let mut order = (0..v.len()).collect::<Vec<usize>>();
+            order.sort_by(|&a, &b| v[a].total_cmp(&v[b]).then(a.cmp(&b)));
+            lehmer_code(&order)
+        })
+        .collect()
+}
+
+/// Index of a permutation in the factorial number system
+fn lehmer_code(perm: &[usize]) -> usize {
+    perm.iter()
+        .enumerate()
+        .fold(0, |code, (i, &p)| {
+            let smaller = perm[i+1..].iter().filter(|&&q| q < p).count();
+            code * (perm.len() - i) + smaller
+        })
+}
+
+/// Number of phrases of the Lempel–Ziv (1976) parsing of a symbol sequence
+///
+/// It is computed by the algorithm of Kaspar and Schuster.
+pub fn lz76_phrases(symbols: &[usize]) -> usize {
+    let n = symbols.len();
+    if n < 2 {
+        return n;
+    }
+    let (mut c, mut l, mut i, mut k, mut k_max) = (1, 1, 0, 1, 1);
+    loop {
+        if symbols[i+k-1] == symbols[l+k-1] {
+            k += 1;
+            if l + k > n {
+                c += 1;
+                break;
+            }
+        } else {
+            k_max = k_max.max(k);
+            i += 1;
+            if i == l {
+                c += 1;
+                l += k_max;
+                if l + 1 > n {
+                    break;
+                }
+                i = 0;
+                k = 1;
+                k_max = 1;
+            } else {
+                k = 1;
+            }
+        }
+    }
+    c
+}
+
+/// Computes the Lempel–Ziv complexity of the ordinal patterns of the embedded series
+///
+/// The number of phrases $c$ of the sequence of $n$ ordinal patterns is normalized by
+/// $n / \log_{d!} n$, the asymptotic number for an i.i.d. uniform sequence over the $d!$
+/// patterns, so the complexity is close to 0 for a periodic series. It stays below 1 for white
+/// noise because the patterns of overlapping windows are neither independent nor uniform.
+/// It is 0 if there are fewer than two patterns or less than two possible patterns.
+pub fn lz_complexity<C>(series: &[f64], coord: &C) -> f64
+where
+    C: DelayCoordinates,
+{
+    let symbols = ordinal_patterns(coord, series);
+    let alphabet = (1..=coord.dimension()).product::<usize>();
+    let n = symbols.len();
+    if n < 2 || alphabet < 2 {
+        return 0.0;
+    }
+    let c = lz76_phrases(&symbols) as f64;
+    c * (n as f64).ln() / (alphabet as f64).ln() / n as f64
+}
+
+#[cfg(test)]
+mod test {
+    use crate::ForwardDelayCoordinates;
+    use crate::symbolic::{lz76_phrases, lz_complexity, ordinal_patterns};
+    use crate::testutil::{sine, white_noise};
+
+    #[test]
+    fn test_ordinal_patterns() {
+        let coord = ForwardDelayCoordinates {
+            delay: 1,
+            dimension: 3,
+        };
+        // Windows (x(t+2), x(t+1), x(t)) = (3, 2, 1), (1, 3, 2) and (2, 1, 3)
+        let patterns = ordinal_patterns(&coord, &[1.0, 2.0, 3.0, 1.0, 2.0]);
+        assert_eq!(patterns, vec![5, 1, 2]);
+        let constant = ordinal_patterns(&coord, &[1.0; 4]);
+        assert_eq!(constant, vec![0, 0]);
+        // NaN is the largest coordinate, so (NaN, 2, 1) has the pattern of (3, 2, 1)
+        let with_nan = ordinal_patterns(&coord, &[1.0, 2.0, f64::NAN]);
+        assert_eq!(with_nan, vec![5]);
+    }
+
+    #[test]
+    fn test_lz76_phrases() {
+        // 0 | 001 | 10 | 100 | 1000 | 101
+        assert_eq!(lz76_phrases(&[0, 0, 0, 1, 1, 0, 1, 0, 0, 1, 0, 0, 0, 1, 0, 1]), 6);
+        assert_eq!(lz76_phrases(&[1; 10]), 2);
+        assert_eq!(lz76_phrases(&[]), 0);
+    }
+
+    #[test]
+    fn test_lz_complexity() {
+        let coord = ForwardDelayCoordinates {
+            delay: 1,
+            dimension: 3,
+        };
+        let periodic = lz_complexity(&sine(2000, 25.0), &coord);
+        let random = lz_complexity(&white_noise(2000, 9), &coord);
+        assert!(periodic < 0.1);
+        assert!(random > 0.5);
+    }
+}
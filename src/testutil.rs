@@ -0,0 +1,16 @@
+//! Helpers shared by the unit tests
+
+use crate::rng::Xorshift;
+
+/// Generates uniform white noise on $[-1, 1)$ with a xorshift generator
+pub fn white_noise(len: usize, seed: u64) -> Vec<f64> {
+    let mut rng = Xorshift::new(seed);
+    (0..len).map(|_| 2.0 * rng.next_f64() - 1.0).collect()
+}
+
+/// Samples a sine wave with the given period in steps
+pub fn sine(len: usize, period: f64) -> Vec<f64> {
+    (0..len)
+        .map(|n| (2.0 * std::f64::consts::PI * n as f64 / period).sin())
+        .collect()
+}
@@ -0,0 +1,32 @@
+use std::process::Command;
+
+#[test]
+fn test_dimension_subcommand() {
+    let path = std::env::temp_dir().join("delay-coord-cli-dimension.csv");
+    let text = (0..1000)
+        .map(|t| format!("{}\n", (t as f64 / 5.0).sin()))
+        .collect::<String>();
+    std::fs::write(&path, text).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_delay-coord"))
+        .args(["dimension", "-d", "8", "-m", "3", "--theiler", "10"])
+        .arg(&path)
+        .output()
+        .unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let mut lines = stdout.lines();
+    let dimension = lines.next().unwrap()
+        .strip_prefix("dimension ").unwrap()
+        .parse::<f64>().unwrap();
+    assert!((dimension - 1.0).abs() < 0.15);
+    let range = lines.next().unwrap()
+        .strip_prefix("scaling_range ").unwrap()
+        .split(' ')
+        .map(|r| r.parse::<f64>().unwrap())
+        .collect::<Vec<f64>>();
+    assert_eq!(range.len(), 2);
+    assert!(range[0] < range[1]);
+}